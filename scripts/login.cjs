@@ -0,0 +1,34 @@
+const { chromium } = require('playwright');
+const protocol = require('./protocol.cjs');
+
+// Interactive login for `login_session`: opens the site's login page and,
+// once the user is through, saves the browser session to PERSONALIZ_STORAGE_STATE.
+const SITES = {
+    linkedin: { login: 'https://www.linkedin.com/login', loggedIn: '**/feed/**' },
+};
+
+protocol.run(async () => {
+    const name = process.argv[2];
+    const site = SITES[name];
+    if (!site) {
+        throw Object.assign(new Error(`Unknown site '${name}' (expected one of: ${Object.keys(SITES).join(', ')})`), { code: 'usage', retryable: false });
+    }
+    if (!protocol.storageState) {
+        throw Object.assign(new Error('No PERSONALIZ_STORAGE_STATE file to save the session to'), { code: 'usage', retryable: false });
+    }
+
+    const browser = await chromium.launch({ headless: false });
+    const context = await protocol.newContext(browser);
+    const page = await context.newPage();
+
+    try {
+        await page.goto(site.login);
+        protocol.progress(`Log in to ${name} in the browser window...`);
+        // Already logged in with the stored session, or waiting for the user
+        await page.waitForURL(site.loggedIn, { timeout: 290000 });
+        await protocol.saveSession(context);
+        return `Logged in to ${name}`;
+    } finally {
+        await browser.close();
+    }
+});
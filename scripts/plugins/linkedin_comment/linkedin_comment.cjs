@@ -0,0 +1,70 @@
+const { chromium } = require('playwright');
+const protocol = require('protocol.cjs');
+
+protocol.run(async () => {
+    // The app passes {"hashtag","comment"} as JSON; a bare hashtag still works when run by hand
+    const defaultComment = "Check out our new GitHub repo for OpenClaw! If you're non-technical, try the desktop app for easy automation. 🚀";
+    let input = { hashtag: process.argv[2] };
+    try {
+        input = JSON.parse(process.argv[2]);
+    } catch {
+        // not JSON
+    }
+    const hashtag = input.hashtag || "#openclaw";
+    const commentText = input.comment || defaultComment;
+    if (protocol.alreadyPublished()) {
+        await protocol.publish(async () => {});
+        return `Already commented (${protocol.idempotencyKey})`;
+    }
+
+    const site = protocol.siteUrl('linkedin', 'https://www.linkedin.com');
+    protocol.progress(`${protocol.dryRun ? 'Dry run - searching mock LinkedIn' : 'Searching'} for hashtag: ${hashtag}`);
+
+    const browser = await chromium.launch({ headless: protocol.dryRun });
+    const context = await protocol.newContext(browser);
+    const page = await context.newPage();
+
+    try {
+        if (protocol.storageState && !protocol.dryRun) {
+            await page.goto(`${site}/feed/`);
+            if (!page.url().includes('/feed')) {
+                throw protocol.sessionExpired('LinkedIn');
+            }
+        } else if (!protocol.dryRun) {
+            // Run by hand without a saved session: wait for a manual login
+            await page.goto(`${site}/login`);
+            protocol.progress("Waiting for user to be on feed...");
+            await page.waitForURL('**/feed/**', { timeout: 60000 });
+        }
+
+        // Search
+        const search = protocol.dryRun ? `${site}/search/results/all/index.html` : `${site}/search/results/all/`;
+        await page.goto(`${search}?keywords=${encodeURIComponent(hashtag)}`);
+        await page.waitForTimeout(protocol.dryRun ? 500 : 5000);
+
+        // Find comment buttons
+        const commentButtons = await page.$$('button.comment-button');
+        protocol.progress(`Found ${commentButtons.length} posts to comment on.`);
+
+        if (commentButtons.length === 0) {
+            return `No posts found for ${hashtag}`;
+        }
+        await commentButtons[0].click();
+        await page.waitForSelector('.ql-editor');
+        await page.fill('.ql-editor', commentText);
+        await protocol.publish(() => page.press('.ql-editor', 'Enter'));
+
+        if (protocol.dryRun) {
+            const commented = await page.evaluate(() => window.__commented[window.__commented.length - 1]);
+            protocol.capture('comment', commented.text, `linkedin:${hashtag}`);
+            return "Dry run: comment captured";
+        }
+        await protocol.saveSession(context);
+        return "Commented on the first post!";
+    } finally {
+        if (!protocol.dryRun) {
+            await new Promise(r => setTimeout(r, 5000));
+        }
+        await browser.close();
+    }
+});
@@ -0,0 +1,63 @@
+const { chromium } = require('playwright');
+const protocol = require('protocol.cjs');
+
+protocol.run(async () => {
+    // The app passes {"content"} as JSON; plain text still works when run by hand
+    let input = { content: process.argv[2] };
+    try {
+        input = JSON.parse(process.argv[2]);
+    } catch {
+        // not JSON
+    }
+    const postContent = input.content || "Hello world from Personaliz!";
+    if (protocol.alreadyPublished()) {
+        await protocol.publish(async () => {});
+        return `Already posted (${protocol.idempotencyKey})`;
+    }
+
+    const site = protocol.siteUrl('linkedin', 'https://www.linkedin.com');
+    protocol.progress(`${protocol.dryRun ? 'Dry run - posting to mock LinkedIn' : 'Posting to LinkedIn'}: ${postContent}`);
+
+    const browser = await chromium.launch({ headless: protocol.dryRun }); // Show browser for demo
+    const context = await protocol.newContext(browser);
+    const page = await context.newPage();
+
+    try {
+        if (protocol.dryRun) {
+            await page.goto(`${site}/feed/index.html`);
+        } else if (protocol.storageState) {
+            await page.goto(`${site}/feed/`);
+            if (!page.url().includes('/feed')) {
+                throw protocol.sessionExpired('LinkedIn');
+            }
+        } else {
+            // Run by hand without a saved session: wait for a manual login
+            await page.goto(`${site}/login`);
+            protocol.progress("Waiting for user to be on feed...");
+            await page.waitForURL('**/feed/**', { timeout: 60000 });
+        }
+
+        // click 'Start a post'
+        await page.click('button.artdeco-button--muted.artdeco-button--4.artdeco-button--tertiary.share-box-feed-entry__trigger');
+
+        // Wait for editor
+        await page.waitForSelector('.ql-editor');
+        await page.fill('.ql-editor', postContent);
+
+        // Click post
+        await protocol.publish(() => page.click('.share-actions__primary-action'));
+
+        if (protocol.dryRun) {
+            const posted = await page.evaluate(() => window.__posted[window.__posted.length - 1]);
+            protocol.capture('post', posted, 'linkedin');
+            return "Dry run: post captured";
+        }
+        await protocol.saveSession(context);
+        return "Post successful!";
+    } finally {
+        if (!protocol.dryRun) {
+            await new Promise(r => setTimeout(r, 5000)); // Keep open for 5s to see success
+        }
+        await browser.close();
+    }
+});
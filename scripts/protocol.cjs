@@ -0,0 +1,140 @@
+// Script protocol v1: scripts talk to the desktop app by printing one JSON
+// event per line on stdout. Anything else on stdout is a protocol violation;
+// free-form logging goes to stderr. Every run must end with exactly one
+// `result` or `error` event.
+//
+//   {"v":1,"type":"progress","message":"Opening LinkedIn","percent":10}
+//   {"v":1,"type":"artifact","name":"screenshot","path":"/tmp/shot.png"}
+//   {"v":1,"type":"result","summary":"Posted","data":{...}}
+//   {"v":1,"type":"capture","action":"post","target":"linkedin","content":"..."}
+//   {"v":1,"type":"error","message":"Login timed out","code":"auth","retryable":false}
+//
+// In a dry run (PERSONALIZ_DRY_RUN=1) scripts must not touch the real sites.
+// They drive the local mock pages under PERSONALIZ_MOCK_DIR instead and report
+// what would have been published with `capture`.
+//
+// Scripts that act as a logged-in user get a Playwright storage state file in
+// PERSONALIZ_STORAGE_STATE. Open the browser context with `newContext`, call
+// `saveSession` after a successful run so refreshed cookies are kept, and throw
+// `sessionExpired()` when the site asks for a login anyway.
+//
+// Scripts that publish wrap the step that makes content public (clicking Post,
+// the API call) in `publish`. It sends a `publishing` checkpoint before the
+// step and `published` after it; the app only retries a failed post when the
+// script never reached `publishing`, and counts anything after `published`
+// as posted even if the script fails while cleaning up.
+//
+//   {"v":1,"type":"publishing"}
+//   {"v":1,"type":"published","already":false}
+//
+// When publishing an approved draft, PERSONALIZ_IDEMPOTENCY_KEY identifies it
+// and stays the same across retries. `publish` records the key in the ledger
+// file PERSONALIZ_PUBLISH_LEDGER once the step is done and skips the step for
+// a key already recorded there; scripts can check `alreadyPublished()` up
+// front to avoid even opening the site.
+
+const path = require("path");
+const { pathToFileURL } = require("url");
+
+const VERSION = 1;
+const dryRun = process.env.PERSONALIZ_DRY_RUN === "1";
+const storageState = process.env.PERSONALIZ_STORAGE_STATE || null;
+const idempotencyKey = process.env.PERSONALIZ_IDEMPOTENCY_KEY || null;
+const ledger = process.env.PERSONALIZ_PUBLISH_LEDGER || null;
+
+function emit(event) {
+    process.stdout.write(JSON.stringify({ v: VERSION, ...event }) + "\n");
+}
+
+const progress = (message, percent) => emit({ type: "progress", message, percent });
+const artifact = (name, details = {}) => emit({ type: "artifact", name, ...details });
+const result = (summary, data) => emit({ type: "result", summary, data });
+const capture = (action, content, target) => emit({ type: "capture", action, content, target });
+const error = (message, details = {}) => emit({ type: "error", message, ...details });
+
+// Resolves once the event has been handed to the app, so a checkpoint is
+// never lost if the process dies right after it.
+function emitNow(event) {
+    return new Promise((resolve) => {
+        process.stdout.write(JSON.stringify({ v: VERSION, ...event }) + "\n", () => resolve());
+    });
+}
+
+function alreadyPublished() {
+    if (!idempotencyKey || !ledger) {
+        return false;
+    }
+    const fs = require("fs");
+    try {
+        return fs.readFileSync(ledger, "utf8").split("\n").includes(idempotencyKey);
+    } catch {
+        return false;
+    }
+}
+
+// Runs `step`, the action that makes content public, between the `publishing`
+// and `published` checkpoints. Returns false without running it when an
+// earlier attempt with the same idempotency key already published.
+async function publish(step) {
+    if (alreadyPublished()) {
+        await emitNow({ type: "published", already: true });
+        return false;
+    }
+    await emitNow({ type: "publishing" });
+    await step();
+    if (idempotencyKey && ledger) {
+        require("fs").appendFileSync(ledger, idempotencyKey + "\n");
+    }
+    await emitNow({ type: "published", already: false });
+    return true;
+}
+
+// Base URL for `site`: the real one, or its mock page directory in a dry run.
+function siteUrl(site, realUrl) {
+    if (!dryRun) {
+        return realUrl;
+    }
+    const dir = process.env.PERSONALIZ_MOCK_DIR || path.join(__dirname, "mock");
+    return pathToFileURL(path.join(dir, site)).href;
+}
+
+// A browser context logged in with the stored session, if there is one.
+async function newContext(browser) {
+    const fs = require("fs");
+    const usable = storageState && fs.existsSync(storageState) && fs.statSync(storageState).size > 0;
+    return browser.newContext(usable ? { storageState } : {});
+}
+
+async function saveSession(context) {
+    if (storageState) {
+        await context.storageState({ path: storageState });
+    }
+}
+
+function sessionExpired(site) {
+    const e = new Error(`The saved ${site} login has expired; log in again from Settings`);
+    e.code = "session_expired";
+    e.retryable = false;
+    return e;
+}
+
+// Runs `main`, reporting its return value as the result and any exception as
+// an error event with a non-zero exit code.
+function run(main) {
+    main()
+        .then((summary) => {
+            result(summary || "done");
+        })
+        .catch((e) => {
+            console.error(e);
+            error(e && e.message ? e.message : String(e), { code: e && e.code, retryable: e && e.retryable });
+            process.exitCode = 1;
+        });
+}
+
+module.exports = {
+    VERSION, dryRun, storageState, idempotencyKey,
+    emit, progress, artifact, result, capture, error,
+    alreadyPublished, publish,
+    siteUrl, newContext, saveSession, sessionExpired, run,
+};
@@ -0,0 +1,627 @@
+use crate::db::{self, Agent, Approval, DbState};
+use crate::openclaw::OpenClawResult;
+use crate::policy::{self, ContentPolicy, Verdict, Violation};
+use crate::runner::Runner;
+use crate::stream::Streams;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+// Sent whenever approvals leave the pending list without the user acting on them
+pub const APPROVALS_CHANGED_EVENT: &str = "approvals-changed";
+pub const APPROVAL_REMINDER_EVENT: &str = "approval-reminder";
+
+const APPROVAL_COLUMNS: &str = "id, agent_id, content, status, draft, created_at, expires_at, superseded_by, run_id, attempts, error, policy_json";
+
+// Word diffs beyond this many cells (old words x new words) fall back to a
+// plain replace; drafts are short, so this only guards against pasted essays.
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", content = "text", rename_all = "snake_case")]
+pub enum DiffOp {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+}
+
+// Per-agent handling of pending approvals, stored as JSON in
+// `agents.approval_policy`, e.g. {"ttl_secs":86400,"remind_every_secs":3600,"supersede":true}.
+// The default keeps approvals until someone decides on them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ApprovalPolicy {
+    // Pending approvals older than this become Expired and can no longer be approved
+    pub ttl_secs: Option<u64>,
+    // How often the UI is reminded of an approval while it stays pending
+    pub remind_every_secs: Option<u64>,
+    // A new draft from the agent rejects the ones still pending
+    pub supersede: bool,
+}
+
+impl ApprovalPolicy {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.ttl_secs == Some(0) {
+            return Err("ttl_secs must be positive".into());
+        }
+        if self.remind_every_secs == Some(0) {
+            return Err("remind_every_secs must be positive".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApprovalReminder {
+    pub approval_id: i32,
+    pub agent_id: i32,
+    pub agent_name: String,
+    pub pending_since: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApprovalRevision {
+    pub revision: i32,
+    pub author: String,
+    pub created_at: String,
+    pub content: String,
+    // Changes against the previous revision, or the draft for the first one
+    pub diff: Vec<DiffOp>,
+}
+
+// Splits into words with their trailing whitespace, so joining the pieces of a
+// diff gives back the exact text.
+fn words(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut in_space = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_space = true;
+        } else if in_space {
+            pieces.push(&text[start..i]);
+            start = i;
+            in_space = false;
+        }
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+fn push(ops: &mut Vec<DiffOp>, op: DiffOp) {
+    match (ops.last_mut(), op) {
+        (Some(DiffOp::Equal(a)), DiffOp::Equal(b)) | (Some(DiffOp::Insert(a)), DiffOp::Insert(b)) | (Some(DiffOp::Delete(a)), DiffOp::Delete(b)) => {
+            a.push_str(&b)
+        }
+        (_, op) => ops.push(op),
+    }
+}
+
+// Word-level diff from `old` to `new` (longest common subsequence).
+pub fn diff(old: &str, new: &str) -> Vec<DiffOp> {
+    let (a, b) = (words(old), words(new));
+    if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+        return vec![DiffOp::Delete(old.to_string()), DiffOp::Insert(new.to_string())];
+    }
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push(&mut ops, DiffOp::Equal(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            push(&mut ops, DiffOp::Delete(a[i].to_string()));
+            i += 1;
+        } else {
+            push(&mut ops, DiffOp::Insert(b[j].to_string()));
+            j += 1;
+        }
+    }
+    ops
+}
+
+// Name recorded on revisions when the UI does not send one.
+fn local_user() -> String {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "reviewer".to_string())
+}
+
+// Queues a draft for review; `content` starts out as the draft and changes with
+// each edit. `violations` are the content policy rules that held it, if any.
+// Under a superseding policy the agent's older pending drafts are rejected.
+pub fn create(conn: &Connection, agent: &Agent, content: &str, violations: &[Violation]) -> Result<i64> {
+    let policy = &agent.approval_policy;
+    let expires_at = policy.ttl_secs.map(|ttl| db::format_timestamp(Utc::now() + chrono::Duration::seconds(ttl as i64)));
+    let violations = serde_json::to_string(violations).unwrap_or_else(|_| "[]".into());
+    conn.execute(
+        "INSERT INTO approvals (agent_id, content, draft, created_at, expires_at, policy_json) VALUES (?1, ?2, ?2, CURRENT_TIMESTAMP, ?3, ?4)",
+        params![agent.id, content, expires_at, violations],
+    )?;
+    let id = conn.last_insert_rowid();
+    if policy.supersede {
+        let superseded = conn.execute(
+            "UPDATE approvals SET status = 'Rejected', superseded_by = ?1 WHERE agent_id = ?2 AND status = 'Pending' AND id != ?1",
+            params![id, agent.id],
+        )?;
+        if superseded > 0 {
+            db::log_event(conn, Some(agent.id), &format!("Rejected {} pending approval(s) superseded by approval {}", superseded, id), "Info")?;
+        }
+    }
+    Ok(id)
+}
+
+// Expires overdue approvals and sends the reminders that are due. Called on
+// every scheduler tick, so both happen within a tick of their time.
+pub fn sweep(state: &DbState, streams: &Streams) {
+    let now = Utc::now();
+    let (expired, reminders) = {
+        let conn = state.0.lock().unwrap();
+        let expired = expire_due(&conn, &db::format_timestamp(now)).unwrap_or_default();
+        let reminders = due_reminders(&conn, now).unwrap_or_default();
+        (expired, reminders)
+    };
+    if expired > 0 {
+        streams.emit(APPROVALS_CHANGED_EVENT, ());
+    }
+    for reminder in reminders {
+        streams.emit(APPROVAL_REMINDER_EVENT, reminder);
+    }
+}
+
+fn expire_due(conn: &Connection, now: &str) -> Result<usize> {
+    let mut stmt = conn.prepare("SELECT id, agent_id, expires_at FROM approvals WHERE status = 'Pending' AND expires_at <= ?")?;
+    let due = stmt
+        .query_map([now], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<Result<Vec<_>>>()?;
+    let mut expired = 0;
+    for (id, agent_id, expires_at) in &due {
+        if transition(conn, *id, &["Pending"], "Expired")? {
+            db::log_event(conn, Some(*agent_id), &format!("Approval {} expired unanswered at {}", id, expires_at), "Info")?;
+            expired += 1;
+        }
+    }
+    Ok(expired)
+}
+
+fn due_reminders(conn: &Connection, now: chrono::DateTime<Utc>) -> Result<Vec<ApprovalReminder>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.agent_id, g.name, a.created_at, a.expires_at, COALESCE(a.reminded_at, a.created_at), g.approval_policy
+         FROM approvals a JOIN agents g ON g.id = a.agent_id
+         WHERE a.status = 'Pending' AND g.approval_policy IS NOT NULL",
+    )?;
+    let pending = stmt
+        .query_map([], |row| {
+            let reminder = ApprovalReminder {
+                approval_id: row.get(0)?,
+                agent_id: row.get(1)?,
+                agent_name: row.get(2)?,
+                pending_since: row.get(3)?,
+                expires_at: row.get(4)?,
+            };
+            Ok((reminder, row.get::<_, Option<String>>(5)?, row.get::<_, String>(6)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut due = Vec::new();
+    for (reminder, last, policy) in pending {
+        let Some(every) = serde_json::from_str::<ApprovalPolicy>(&policy).ok().and_then(|p| p.remind_every_secs) else {
+            continue;
+        };
+        let last = last.as_deref().and_then(db::parse_timestamp);
+        if last.is_some_and(|t| now - t < chrono::Duration::seconds(every as i64)) {
+            continue;
+        }
+        conn.execute("UPDATE approvals SET reminded_at = ? WHERE id = ?", params![db::format_timestamp(now), reminder.approval_id])?;
+        due.push(reminder);
+    }
+    Ok(due)
+}
+
+pub fn revisions(conn: &Connection, approval_id: i32) -> Result<Vec<ApprovalRevision>> {
+    let mut stmt = conn.prepare(
+        "SELECT revision, author, created_at, content, diff_json FROM approval_revisions
+         WHERE approval_id = ? ORDER BY revision",
+    )?;
+    let rows = stmt.query_map([approval_id], |row| {
+        let diff: String = row.get(4)?;
+        Ok(ApprovalRevision {
+            revision: row.get(0)?,
+            author: row.get(1)?,
+            created_at: row.get(2)?,
+            content: row.get(3)?,
+            diff: serde_json::from_str(&diff).unwrap_or_default(),
+        })
+    })?;
+    rows.collect()
+}
+
+fn approval_from_row(row: &rusqlite::Row) -> Result<Approval> {
+    Ok(Approval {
+        id: row.get(0)?,
+        agent_id: row.get(1)?,
+        content: row.get(2)?,
+        status: row.get(3)?,
+        draft: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+        created_at: row.get(5)?,
+        expires_at: row.get(6)?,
+        superseded_by: row.get(7)?,
+        run_id: row.get(8)?,
+        attempts: row.get(9)?,
+        error: row.get(10)?,
+        policy_violations: row
+            .get::<_, Option<String>>(11)?
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default(),
+        revisions: Vec::new(),
+    })
+}
+
+pub fn get(conn: &Connection, id: i32) -> Result<Option<Approval>> {
+    let approval = conn
+        .query_row(&format!("SELECT {} FROM approvals WHERE id = ?", APPROVAL_COLUMNS), [id], approval_from_row)
+        .optional()?;
+    approval
+        .map(|a| Ok(Approval { revisions: revisions(conn, a.id)?, ..a }))
+        .transpose()
+}
+
+// Key the runner and scripts use for posting an approval, the same on every attempt.
+pub fn idempotency_key(approval_id: i32) -> String {
+    format!("approval-{}", approval_id)
+}
+
+// Records that `key` is being published. Only a key never seen before, or one
+// whose last attempt is known to have failed before anything went out, can be
+// claimed; otherwise the status it already has (Publishing, Published or
+// Unknown) is returned and nothing must be posted.
+pub fn claim_publication(conn: &Connection, key: &str, approval_id: i32) -> Result<Option<String>> {
+    let claimed = conn.execute(
+        "INSERT INTO publications (idempotency_key, approval_id, status, started_at) VALUES (?, ?, 'Publishing', CURRENT_TIMESTAMP)
+         ON CONFLICT (idempotency_key) DO UPDATE SET status = 'Publishing', run_id = NULL, started_at = CURRENT_TIMESTAMP, finished_at = NULL
+         WHERE publications.status = 'Failed'",
+        params![key, approval_id],
+    )?;
+    if claimed == 1 {
+        return Ok(None);
+    }
+    conn.query_row("SELECT status FROM publications WHERE idempotency_key = ?", [key], |row| row.get(0)).map(Some)
+}
+
+pub fn link_run(conn: &Connection, approval_id: i32, run_id: i64) -> Result<()> {
+    conn.execute("UPDATE approvals SET run_id = ? WHERE id = ?", params![run_id, approval_id])?;
+    conn.execute("UPDATE publications SET run_id = ? WHERE idempotency_key = ?", params![run_id, idempotency_key(approval_id)])?;
+    Ok(())
+}
+
+// Records how posting an approval went. `script` is the publishing script's
+// result, if it got to run: a failure after its `published` checkpoint still
+// counts as posted, and one after `publishing` may or may not have gone out.
+// Only failures before `publishing` leave the approval open to a retry.
+pub fn settle(
+    conn: &Connection,
+    approval_id: i32,
+    agent_id: i32,
+    outcome: &std::result::Result<String, String>,
+    script: Option<&OpenClawResult>,
+) -> Result<()> {
+    let key = idempotency_key(approval_id);
+    let (status, publication, error, message, level) = match outcome {
+        Ok(_) => ("Posted", "Published", None, format!("Posted approval {}", approval_id), "Info"),
+        Err(e) if script.is_some_and(|s| s.published()) => (
+            "Posted",
+            "Published",
+            None,
+            format!("Posted approval {}, but the script failed afterwards: {}", approval_id, e),
+            "Error",
+        ),
+        Err(e) if script.is_some_and(|s| s.publishing()) => (
+            "Unknown",
+            "Unknown",
+            Some(format!("Posting stopped while publishing, so it may have gone out; check the site: {}", e)),
+            format!("Posting approval {} stopped part way: {}", approval_id, e),
+            "Error",
+        ),
+        Err(e) => ("Failed", "Failed", Some(e.clone()), format!("Posting approval {} failed: {}", approval_id, e), "Error"),
+    };
+    conn.execute("UPDATE approvals SET status = ?, error = ? WHERE id = ?", params![status, error, approval_id])?;
+    conn.execute(
+        "UPDATE publications SET status = ?, finished_at = CURRENT_TIMESTAMP WHERE idempotency_key = ?",
+        params![publication, key],
+    )?;
+    db::log_event(conn, Some(agent_id), &message, level)
+}
+
+// Brings an approval whose key was already claimed in line with the publication.
+pub fn settle_existing(conn: &Connection, approval_id: i32, publication: &str) -> Result<()> {
+    let (status, error) = match publication {
+        "Published" => ("Posted", None),
+        "Unknown" => ("Unknown", Some("An earlier attempt may have posted it; check the site")),
+        // Still being posted by another run, which settles it
+        _ => return Ok(()),
+    };
+    conn.execute("UPDATE approvals SET status = ?, error = ? WHERE id = ?", params![status, error, approval_id])?;
+    Ok(())
+}
+
+// Approvals still Executing at startup were cut off when the app closed. Those
+// that had claimed their key may have been posted, so they are left Unknown
+// until someone checks; the rest never started and can simply be retried.
+pub fn recover_interrupted(conn: &Connection) -> Result<usize> {
+    let publishing = "SELECT approval_id FROM publications WHERE status = 'Publishing'";
+    let unknown = conn.execute(
+        &format!(
+            "UPDATE approvals SET status = 'Unknown', error = 'Interrupted while posting, so it may have gone out; check the site'
+             WHERE status = 'Executing' AND id IN ({})",
+            publishing
+        ),
+        [],
+    )?;
+    conn.execute("UPDATE publications SET status = 'Unknown', finished_at = CURRENT_TIMESTAMP WHERE status = 'Publishing'", [])?;
+    conn.execute(
+        "UPDATE approvals SET status = 'Posted', error = NULL
+         WHERE status = 'Executing' AND id IN (SELECT approval_id FROM publications WHERE status = 'Published')",
+        [],
+    )?;
+    let failed = conn.execute(
+        "UPDATE approvals SET status = 'Failed', error = 'Interrupted before posting started' WHERE status = 'Executing'",
+        [],
+    )?;
+    Ok(unknown + failed)
+}
+
+// Moves an approval from one of `from` to `to` in a single statement, so of
+// two callers racing on the same approval only one gets `true`.
+fn transition(conn: &Connection, id: i32, from: &[&str], to: &str) -> Result<bool> {
+    let placeholders = vec!["?"; from.len()].join(", ");
+    let sql = format!("UPDATE approvals SET status = ? WHERE id = ? AND status IN ({})", placeholders);
+    let mut values: Vec<&dyn rusqlite::ToSql> = vec![&to, &id];
+    values.extend(from.iter().map(|s| s as &dyn rusqlite::ToSql));
+    Ok(conn.execute(&sql, values.as_slice())? == 1)
+}
+
+// Pending, Executing, Failed and Unknown approvals: the ones that still need attention.
+#[tauri::command]
+pub fn get_approvals(state: State<DbState>) -> std::result::Result<Vec<Approval>, String> {
+    let conn = state.0.lock().unwrap();
+    let mut stmt = conn
+        .prepare(&format!("SELECT {} FROM approvals WHERE status IN ('Pending', 'Executing', 'Failed', 'Unknown') ORDER BY id", APPROVAL_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let pending = stmt
+        .query_map([], approval_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    pending
+        .into_iter()
+        .map(|a| Ok(Approval { revisions: revisions(&conn, a.id).map_err(|e| e.to_string())?, ..a }))
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_approval(state: State<DbState>, id: i32) -> std::result::Result<Approval, String> {
+    let conn = state.0.lock().unwrap();
+    get(&conn, id).map_err(|e| e.to_string())?.ok_or_else(|| format!("Approval {} not found", id))
+}
+
+// Replaces the text that will be posted. The draft and every edit are kept.
+#[tauri::command]
+pub fn edit_approval(state: State<DbState>, id: i32, content: String, author: Option<String>) -> std::result::Result<ApprovalRevision, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("The approval text cannot be empty".into());
+    }
+    let author = author.map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).unwrap_or_else(local_user);
+
+    let mut conn = state.0.lock().unwrap();
+    let approval = get(&conn, id).map_err(|e| e.to_string())?.ok_or_else(|| format!("Approval {} not found", id))?;
+    if approval.status != "Pending" {
+        return Err(format!("Approval {} is {} and can no longer be edited", id, approval.status));
+    }
+    if approval.content == content {
+        return Err("The edit does not change the text".into());
+    }
+    let agent = db::get_agent(&conn, approval.agent_id).map_err(|e| e.to_string())?;
+    let (spec, kind) = crate::runner::resolve(&agent)?;
+    kind.check_draft(content)?;
+    // Edits are screened like new drafts; what would only be held is shown to the reviewer
+    let policy = ContentPolicy::load(&conn)?;
+    let violations = match policy.verdict(policy.evaluate(&conn, &kind.platform(&spec), &kind.policy_text(content))?) {
+        Verdict::Block(violations) => return Err(format!("The edit breaks the content policy: {}", policy::describe(&violations))),
+        Verdict::Hold(violations) => violations,
+        Verdict::Allow => Vec::new(),
+    };
+
+    let diff = diff(&approval.content, content);
+    let revision = approval.revisions.last().map_or(1, |r| r.revision + 1);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO approval_revisions (approval_id, revision, author, content, diff_json, created_at)
+         VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+        params![id, revision, author, content, serde_json::to_string(&diff).map_err(|e| e.to_string())?],
+    ).map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE approvals SET content = ?, policy_json = ? WHERE id = ?",
+        params![content, serde_json::to_string(&violations).map_err(|e| e.to_string())?, id],
+    ).map_err(|e| e.to_string())?;
+    let _ = db::log_event(&tx, Some(approval.agent_id), &format!("{} edited approval {} (revision {})", author, id, revision), "Info");
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let created_at = conn
+        .query_row("SELECT created_at FROM approval_revisions WHERE approval_id = ? AND revision = ?", params![id, revision], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(ApprovalRevision { revision, author, created_at, content: content.to_string(), diff })
+}
+
+// Approving queues the current text, which includes any edits, for posting and
+// returns at once; the approval moves on to Posted or Failed when the run ends.
+// Approvals past their expiry are expired here rather than posted, even before
+// the next sweep. Deciding twice, e.g. a double click, fails the second time.
+#[tauri::command]
+pub fn approve_request(state: State<DbState>, runner: State<Runner>, id: i32, approved: bool) -> std::result::Result<(), String> {
+    let approval = {
+        let mut conn = state.0.lock().unwrap();
+        let approval = get(&conn, id).map_err(|e| e.to_string())?.ok_or_else(|| format!("Approval {} not found", id))?;
+        if let Some(expires_at) = approval.expires_at.as_deref().filter(|t| db::parse_timestamp(t).is_some_and(|t| t <= Utc::now())) {
+            if approval.status == "Pending" {
+                expire_due(&conn, expires_at).map_err(|e| e.to_string())?;
+                return Err(format!("Approval {} expired at {}", id, expires_at));
+            }
+        }
+        if !approved {
+            if !transition(&conn, id, &["Pending"], "Rejected").map_err(|e| e.to_string())? {
+                return Err(already_decided(&conn, id));
+            }
+            return Ok(());
+        }
+        begin_posting(&mut conn, approval, "Pending")?
+    };
+    runner.enqueue_approval(approval);
+    Ok(())
+}
+
+// Posts a Failed approval again. Unknown ones must be confirmed first.
+#[tauri::command]
+pub fn retry_approval(state: State<DbState>, runner: State<Runner>, id: i32) -> std::result::Result<(), String> {
+    let approval = {
+        let mut conn = state.0.lock().unwrap();
+        let approval = get(&conn, id).map_err(|e| e.to_string())?.ok_or_else(|| format!("Approval {} not found", id))?;
+        begin_posting(&mut conn, approval, "Failed")?
+    };
+    runner.enqueue_approval(approval);
+    Ok(())
+}
+
+// Settles an Unknown approval once the user has checked the site. Confirming
+// it was not posted makes it Failed, which can then be retried.
+#[tauri::command]
+pub fn confirm_approval(state: State<DbState>, id: i32, posted: bool) -> std::result::Result<(), String> {
+    let mut conn = state.0.lock().unwrap();
+    let approval = get(&conn, id).map_err(|e| e.to_string())?.ok_or_else(|| format!("Approval {} not found", id))?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let to = if posted { "Posted" } else { "Failed" };
+    if !transition(&tx, id, &["Unknown"], to).map_err(|e| e.to_string())? {
+        return Err(format!("Approval {} is {}; only posts with an unknown outcome need confirming", id, approval.status));
+    }
+    let (publication, error) = if posted { ("Published", None) } else { ("Failed", Some("Confirmed as not posted")) };
+    tx.execute("UPDATE approvals SET error = ? WHERE id = ?", params![error, id]).map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE publications SET status = ?, finished_at = CURRENT_TIMESTAMP WHERE idempotency_key = ?",
+        params![publication, idempotency_key(id)],
+    ).map_err(|e| e.to_string())?;
+    if posted {
+        // Counts for the duplicate check like any other post
+        let agent = db::get_agent(&tx, approval.agent_id).map_err(|e| e.to_string())?;
+        if let Ok((spec, kind)) = crate::runner::resolve(&agent) {
+            policy::record_published(&tx, agent.id, &kind.platform(&spec), &kind.policy_text(&approval.content)).map_err(|e| e.to_string())?;
+        }
+    }
+    let message = format!("Approval {} confirmed as {}", id, if posted { "posted" } else { "not posted" });
+    let _ = db::log_event(&tx, Some(approval.agent_id), &message, "Info");
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn already_decided(conn: &Connection, id: i32) -> String {
+    match get(conn, id) {
+        Ok(Some(approval)) => format!("Approval {} is already {}", id, approval.status),
+        _ => format!("Approval {} not found", id),
+    }
+}
+
+// Moves the approval from `from` to Executing and counts the attempt, in one
+// transaction; the caller then hands the returned approval to the runner.
+fn begin_posting(conn: &mut Connection, approval: Approval, from: &str) -> std::result::Result<Approval, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if !transition(&tx, approval.id, &[from], "Executing").map_err(|e| e.to_string())? {
+        drop(tx);
+        return Err(if approval.status == "Unknown" {
+            format!("Approval {} may already be posted; check the site and confirm before retrying", approval.id)
+        } else if from == "Failed" {
+            format!("Approval {} is {}; only failed posts can be retried", approval.id, approval.status)
+        } else {
+            already_decided(conn, approval.id)
+        });
+    }
+    tx.execute("UPDATE approvals SET attempts = attempts + 1, error = NULL WHERE id = ?", [approval.id]).map_err(|e| e.to_string())?;
+    let _ = db::log_event(&tx, Some(approval.agent_id), &format!("Queued approval {} for posting", approval.id), "Info");
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Approval { status: "Executing".into(), attempts: approval.attempts + 1, error: None, ..approval })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openclaw::ScriptEvent;
+
+    fn setup() -> (Connection, i32) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run(&mut conn, None).unwrap();
+        conn.execute("INSERT INTO agents (name, openclaw_task) VALUES ('poster', '{\"kind\":\"trending_post\"}')", []).unwrap();
+        conn.execute("INSERT INTO approvals (agent_id, content, draft, status) VALUES (1, 'hello', 'hello', 'Executing')", []).unwrap();
+        (conn, 1)
+    }
+
+    fn script(events: Vec<ScriptEvent>) -> OpenClawResult {
+        OpenClawResult { events, ..OpenClawResult::failed("browser closed".into()) }
+    }
+
+    fn statuses(conn: &Connection, id: i32) -> (String, String) {
+        let approval: String = conn.query_row("SELECT status FROM approvals WHERE id = ?", [id], |r| r.get(0)).unwrap();
+        let publication: String = conn
+            .query_row("SELECT status FROM publications WHERE idempotency_key = ?", [idempotency_key(id)], |r| r.get(0))
+            .unwrap();
+        (approval, publication)
+    }
+
+    #[test]
+    fn failure_before_publishing_can_be_claimed_again() {
+        let (conn, id) = setup();
+        let key = idempotency_key(id);
+        assert_eq!(claim_publication(&conn, &key, id).unwrap(), None);
+        settle(&conn, id, 1, &Err("no session".into()), Some(&script(vec![]))).unwrap();
+        assert_eq!(statuses(&conn, id), ("Failed".into(), "Failed".into()));
+        assert_eq!(claim_publication(&conn, &key, id).unwrap(), None);
+    }
+
+    #[test]
+    fn failure_while_publishing_is_unknown_until_confirmed() {
+        let (conn, id) = setup();
+        let key = idempotency_key(id);
+        claim_publication(&conn, &key, id).unwrap();
+        settle(&conn, id, 1, &Err("timed out".into()), Some(&script(vec![ScriptEvent::Publishing]))).unwrap();
+        assert_eq!(statuses(&conn, id), ("Unknown".into(), "Unknown".into()));
+        assert_eq!(claim_publication(&conn, &key, id).unwrap().as_deref(), Some("Unknown"));
+    }
+
+    #[test]
+    fn failure_after_published_counts_as_posted() {
+        let (conn, id) = setup();
+        let key = idempotency_key(id);
+        claim_publication(&conn, &key, id).unwrap();
+        let events = vec![ScriptEvent::Publishing, ScriptEvent::Published { already: false }];
+        settle(&conn, id, 1, &Err("saveSession failed".into()), Some(&script(events))).unwrap();
+        assert_eq!(statuses(&conn, id), ("Posted".into(), "Published".into()));
+        assert_eq!(claim_publication(&conn, &key, id).unwrap().as_deref(), Some("Published"));
+    }
+
+    #[test]
+    fn interrupted_posts_are_unknown_unless_never_claimed() {
+        let (conn, id) = setup();
+        conn.execute("INSERT INTO approvals (agent_id, content, draft, status) VALUES (1, 'queued', 'queued', 'Executing')", []).unwrap();
+        claim_publication(&conn, &idempotency_key(id), id).unwrap();
+        assert_eq!(recover_interrupted(&conn).unwrap(), 2);
+        assert_eq!(statuses(&conn, id), ("Unknown".into(), "Unknown".into()));
+        let queued: String = conn.query_row("SELECT status FROM approvals WHERE id = 2", [], |r| r.get(0)).unwrap();
+        assert_eq!(queued, "Failed");
+    }
+}
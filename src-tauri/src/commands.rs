@@ -1,15 +1,9 @@
-// Use anyhow implicitly or use std::result::Result explicitly
-use chrono::{DateTime, Local, Utc};
-use cron::Schedule;
-use rusqlite::{params, Connection};
+use crate::db::{self, Agent, DbState};
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Command;
-use std::str::FromStr;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
-use uuid::Uuid;
+use tauri::State;
 use which::which;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -22,37 +16,19 @@ pub struct EnvInfo {
     pub has_playwright: bool,
 }
 
-fn db_path() -> std::path::PathBuf {
-    let mut p = dirs::data_local_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-    p.push("personaliz-desktop");
-    std::fs::create_dir_all(&p).ok();
-    p.push("personaliz.db");
-    p
-}
-
-fn get_conn() -> anyhow::Result<Connection> {
-    let path = db_path();
-    let conn = Connection::open(path)?;
-    Ok(conn)
-}
-
 #[tauri::command]
-pub fn detect_env() -> std::result::Result<EnvInfo, String> {
+pub async fn detect_env(scripts: State<'_, crate::scripts::Scripts>) -> std::result::Result<EnvInfo, String> {
     let os = std::env::consts::OS.to_string();
     let has_node = which("node").is_ok();
     let has_pnpm = which("pnpm").is_ok();
-    let has_openclaw = which("openclaw").is_ok();
+    let has_openclaw = crate::openclaw::OpenClawClient::locate().is_ok();
     let has_ollama = which("ollama").is_ok();
-    // detect playwright by checking for `npx playwright --version` availability via pnpm
-    let has_playwright = if has_pnpm {
-        // quick check: attempt `pnpm --version` to confirm pnpm works
-        std::process::Command::new("pnpm")
-            .arg("-v")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    } else {
-        false
+    let has_playwright = has_node && {
+        // `check` runs node, so keep it off the async workers
+        let scripts = scripts.inner().clone();
+        tauri::async_runtime::spawn_blocking(move || scripts.check().has_playwright)
+            .await
+            .map_err(|e| e.to_string())?
     };
 
     Ok(EnvInfo {
@@ -137,97 +113,43 @@ pub fn run_shell_command(cmd: String) -> Result<String, String> {
     }
 }
 
+// Compatibility shims for the command set that used to keep its own UUID-keyed
+// database. They now read and write the shared `DbState` like everything else.
+
 #[tauri::command]
 pub fn db_init() -> Result<String, String> {
-    match get_conn() {
-        Ok(conn) => {
-            conn.execute_batch(
-                "CREATE TABLE IF NOT EXISTS agents (
-                    id TEXT PRIMARY KEY,
-                    name TEXT,
-                    description TEXT,
-                    config_json TEXT,
-                    created_at TEXT
-                );
-                CREATE TABLE IF NOT EXISTS schedules (
-                    id TEXT PRIMARY KEY,
-                    agent_id TEXT,
-                    cron_expr TEXT,
-                    next_run TEXT
-                );
-                CREATE TABLE IF NOT EXISTS logs (
-                    id TEXT PRIMARY KEY,
-                    agent_id TEXT,
-                    level TEXT,
-                    message TEXT,
-                    ts TEXT
-                );
-                CREATE TABLE IF NOT EXISTS settings (
-                    key TEXT PRIMARY KEY,
-                    value TEXT
-                );",
-            )
-            .map_err(|e| e.to_string())?;
-            Ok("ok".into())
-        }
-        Err(e) => Err(e.to_string()),
-    }
+    // The schema is owned by `migrations` and applied at startup
+    Ok("ok".into())
 }
 
 #[tauri::command]
-pub fn create_demo_agents() -> Result<String, String> {
-    let conn = get_conn().map_err(|e| e.to_string())?;
-
-    // Trending LinkedIn Agent
-    let id1 = Uuid::new_v4().to_string();
-    let config1 = serde_json::json!({
-        "role": "trending-researcher",
-        "goal": "Search OpenClaw trending topics and draft a LinkedIn post; request approval before posting.",
-        "automation": {"script":"linkedin_post"},
-        "sandbox": false
-    });
-
-    conn.execute(
-        "INSERT OR REPLACE INTO agents (id, name, description, config_json, created_at) VALUES (?1,?2,?3,?4,?5)",
-        params![
-            &id1,
+pub fn create_demo_agents(state: State<DbState>) -> Result<String, String> {
+    let demos = [
+        (
             "Trending LinkedIn Agent",
             "Find trending OpenClaw topics and prepare LinkedIn post (requires approval)",
-            config1.to_string(),
-            chrono::Utc::now().to_rfc3339()
-        ],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // schedule daily at 09:00
-    let sid1 = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT OR REPLACE INTO schedules (id, agent_id, cron_expr, next_run) VALUES (?1,?2,?3,?4)",
-        params![&sid1, &id1, "0 9 * * *", ""],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Hashtag Comment Agent
-    let id2 = Uuid::new_v4().to_string();
-    let config2 = serde_json::json!({
-        "role": "hashtag-promoter",
-        "goal": "Every hour search LinkedIn for #openclaw and comment promoting the repo; do not post personal data.",
-        "automation": {"script":"linkedin_comment"},
-        "sandbox": false
-    });
-
-    conn.execute(
-        "INSERT OR REPLACE INTO agents (id, name, description, config_json, created_at) VALUES (?1,?2,?3,?4,?5)",
-        params![&id2, "Hashtag Comment Agent", "Hourly search for #openclaw and comment to promote repo", config2.to_string(), chrono::Utc::now().to_rfc3339()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    let sid2 = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT OR REPLACE INTO schedules (id, agent_id, cron_expr, next_run) VALUES (?1,?2,?3,?4)",
-        params![&sid2, &id2, "0 * * * *", ""],
-    )
-    .map_err(|e| e.to_string())?;
+            serde_json::json!({ "kind": "trending_post" }),
+            "0 9 * * *",
+        ),
+        (
+            "Hashtag Comment Agent",
+            "Hourly search for #openclaw and comment to promote repo",
+            serde_json::json!({ "kind": "hashtag_comment", "params": { "hashtag": "#openclaw" } }),
+            "0 * * * *",
+        ),
+    ];
+
+    let conn = state.0.lock().unwrap();
+    for (name, description, spec, schedule) in demos {
+        let task = crate::tasks::validate(&spec.to_string())?;
+        let next_run_at = crate::scheduler::initial_next_run(schedule)?;
+        conn.execute(
+            "INSERT INTO agents (name, description, openclaw_task, schedule, next_run_at, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)",
+            params![name, description, task, schedule, next_run_at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
 
     Ok("demo agents created".into())
 }
@@ -241,149 +163,51 @@ pub struct AgentSummary {
     pub created_at: String,
 }
 
-#[tauri::command]
-pub fn list_agents() -> Result<Vec<AgentSummary>, String> {
-    let conn = get_conn().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT id, name, description, config_json, created_at FROM agents ORDER BY created_at DESC")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([], |r| {
-            let cfg_str: String = r.get(3)?;
-            let cfg: serde_json::Value = serde_json::from_str(&cfg_str).unwrap_or(serde_json::json!({}));
-            Ok(AgentSummary {
-                id: r.get(0)?,
-                name: r.get(1)?,
-                description: r.get(2)?,
-                config: cfg,
-                created_at: r.get(4)?,
-            })
-        })
-        .map_err(|e| e.to_string())?;
-
-    let mut out = Vec::new();
-    for r in rows {
-        out.push(r.map_err(|e| e.to_string())?);
+impl From<Agent> for AgentSummary {
+    fn from(agent: Agent) -> Self {
+        // The task spec doubles as the config the old UUID-based commands returned
+        let config = serde_json::from_str(&agent.openclaw_task)
+            .unwrap_or_else(|_| serde_json::json!({ "task": agent.openclaw_task }));
+        AgentSummary {
+            id: agent.id.to_string(),
+            name: agent.name,
+            description: agent.description,
+            config,
+            created_at: agent.created_at.unwrap_or_default(),
+        }
     }
-    Ok(out)
 }
 
 #[tauri::command]
-pub fn run_agent_now(agent_id: String, sandbox: bool) -> Result<String, String> {
-    let conn = get_conn().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT config_json FROM agents WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
-    let cfg_str: String = stmt
-        .query_row([&agent_id], |r| r.get(0))
-        .map_err(|e| e.to_string())?;
-    let cfg: serde_json::Value = serde_json::from_str(&cfg_str).map_err(|e| e.to_string())?;
-    let automation = cfg
-        .get("automation")
-        .and_then(|a| a.get("script"))
-        .and_then(|s| s.as_str())
-        .unwrap_or("");
-
-    // For demo, automation.script can be "linkedin_post" or "linkedin_comment"
-    let script_cmd = match automation {
-        "linkedin_post" => vec!["run", "playwright:post"],
-        "linkedin_comment" => vec!["run", "playwright:comment"],
-        _ => vec![],
-    };
-
-    if script_cmd.is_empty() {
-        return Err("No automation script configured for this agent".into());
-    }
-
-    // If sandbox, do not actually execute external script; instead simulate output
-    if sandbox {
-        // record a log
-        let _ = conn.execute(
-            "INSERT INTO logs (id, agent_id, level, message, ts) VALUES (?1,?2,?3,?4,?5)",
-            params![Uuid::new_v4().to_string(), &agent_id, "INFO", "Sandbox run - simulated actions", chrono::Utc::now().to_rfc3339()],
-        );
-        return Ok("sandbox: simulated run completed".into());
-    }
-
-    // Attempt to call pnpm to run the script (requires pnpm available in PATH)
-    let pnpm = if which("pnpm").is_ok() { "pnpm" } else { "npm" };
-    let mut cmd = Command::new(pnpm);
-    cmd.args(&script_cmd);
-    // run in project workspace
-    cmd.current_dir(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
+pub fn list_agents(state: State<DbState>) -> Result<Vec<AgentSummary>, String> {
+    let conn = state.0.lock().unwrap();
+    let agents = db::list_agents(&conn, None).map_err(|e| e.to_string())?;
+    Ok(agents.into_iter().rev().map(AgentSummary::from).collect())
+}
 
-    match cmd.output() {
-        Ok(out) => {
-            let stdout = String::from_utf8_lossy(&out.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-            let msg = format!("STDOUT:\n{}\nSTDERR:\n{}", stdout, stderr);
-            let _ = conn.execute(
-                "INSERT INTO logs (id, agent_id, level, message, ts) VALUES (?1,?2,?3,?4,?5)",
-                params![Uuid::new_v4().to_string(), &agent_id, "INFO", &msg, chrono::Utc::now().to_rfc3339()],
-            );
-            Ok(msg)
-        }
-        Err(e) => Err(format!("failed to spawn automation script: {}", e)),
-    }
+#[tauri::command]
+pub async fn run_agent_now(runner: State<'_, crate::runner::Runner>, agent_id: String, sandbox: bool) -> Result<String, String> {
+    let id: i32 = agent_id
+        .parse()
+        .map_err(|_| format!("Unknown agent id: {}", agent_id))?;
+    runner.run_agent_by_id(id, sandbox).await
 }
 
 #[tauri::command]
-pub fn get_logs_legacy(limit: Option<u32>) -> std::result::Result<Vec<HashMap<String, String>>, String> {
-    let conn = get_conn().map_err(|e| e.to_string())?;
-    let lim = limit.unwrap_or(200) as i64;
-    let mut stmt = conn
-        .prepare("SELECT id, agent_id, level, message, ts FROM logs ORDER BY ts DESC LIMIT ?1")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map([lim], |r| {
+pub fn get_logs_legacy(state: State<DbState>, limit: Option<u32>) -> std::result::Result<Vec<HashMap<String, String>>, String> {
+    let conn = state.0.lock().unwrap();
+    let logs = db::list_logs(&conn, limit.unwrap_or(200)).map_err(|e| e.to_string())?;
+    let out = logs
+        .into_iter()
+        .map(|log| {
             let mut m = HashMap::new();
-            m.insert("id".to_string(), r.get::<_, String>(0)?);
-            m.insert("agent_id".to_string(), r.get::<_, String>(1)?);
-            m.insert("level".to_string(), r.get::<_, String>(2)?);
-            m.insert("message".to_string(), r.get::<_, String>(3)?);
-            m.insert("ts".to_string(), r.get::<_, String>(4)?);
-            Ok(m)
+            m.insert("id".to_string(), log.id.to_string());
+            m.insert("agent_id".to_string(), log.agent_id.map(|id| id.to_string()).unwrap_or_default());
+            m.insert("level".to_string(), log.level.to_uppercase());
+            m.insert("message".to_string(), log.message);
+            m.insert("ts".to_string(), log.timestamp);
+            m
         })
-        .map_err(|e| e.to_string())?;
-    let mut out = Vec::new();
-    for r in rows {
-        out.push(r.map_err(|e| e.to_string())?);
-    }
+        .collect();
     Ok(out)
 }
-
-pub fn start_scheduler_thread() {
-    // Spawn a background thread that wakes every 30s and checks schedules
-    thread::spawn(|| loop {
-        if let Ok(conn) = get_conn() {
-            if let Ok(mut stmt) = conn.prepare("SELECT id, agent_id, cron_expr FROM schedules") {
-                let rows = stmt.query_map([], |r| {
-                    Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))
-                });
-                if let Ok(rows) = rows {
-                    for row in rows.flatten() {
-                        let (sid, agent_id, cron_expr) = row;
-                        if let Ok(schedule) = Schedule::from_str(&cron_expr) {
-                            let now: DateTime<Utc> = Utc::now();
-                            if let Some(next) = schedule.upcoming(Utc).next() {
-                                let next_ts = next;
-                                // If next occurrence is within the next 30s, run it
-                                let diff = next_ts.signed_duration_since(now).num_seconds();
-                                if diff <= 30 && diff >= 0 {
-                                    // run agent (best-effort)
-                                    let _ = run_agent_now(agent_id.clone(), false);
-                                    // log next_run update
-                                    let _ = conn.execute(
-                                        "UPDATE schedules SET next_run = ?1 WHERE id = ?2",
-                                        params![next_ts.to_rfc3339(), sid],
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        thread::sleep(Duration::from_secs(30));
-    });
-}
@@ -0,0 +1,171 @@
+use crate::db::{DbState, log_event};
+use crate::llm::{self, ChatMessage};
+use crate::scripts::Scripts;
+use crate::secrets::Vault;
+use crate::{scheduler, tasks};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+// Attempts at getting a valid spec out of the model, counting the first
+const MAX_ATTEMPTS: usize = 2;
+
+// What the model must answer with; `agent_schema` describes the same shape.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct AgentDraft {
+    name: String,
+    #[serde(default)]
+    description: String,
+    task_kind: String,
+    #[serde(default)]
+    params: serde_json::Map<String, serde_json::Value>,
+    schedule: String,
+    requires_approval: bool,
+}
+
+// A compiled agent, ready to hand to `create_agent` once the user confirms it.
+#[derive(Serialize, Debug, Clone)]
+pub struct AgentPreview {
+    pub name: String,
+    pub description: String,
+    pub task: String,
+    pub schedule: String,
+    pub next_run_at: Option<String>,
+    pub requires_approval: bool,
+    // Things the user should know before confirming
+    pub warnings: Vec<String>,
+    pub provider: String,
+}
+
+// JSON schema for `AgentDraft`, built from the registered task kinds.
+pub fn agent_schema(scripts: &Scripts) -> serde_json::Value {
+    let catalog = tasks::catalog(scripts);
+    let kinds: Vec<serde_json::Value> = tasks::kinds()
+        .iter()
+        .map(|k| {
+            let params: serde_json::Map<String, serde_json::Value> = k
+                .inputs()
+                .iter()
+                .map(|i| (i.name.to_string(), serde_json::json!({ "type": "string", "description": i.description })))
+                .collect();
+            let required: Vec<&str> = k.inputs().iter().filter(|i| i.required && i.default.is_none()).map(|i| i.name).collect();
+            let mut then = serde_json::json!({
+                "properties": {
+                    "params": { "type": "object", "properties": params, "required": required, "additionalProperties": false }
+                }
+            });
+            // Only pinned when it does not depend on which plugin is picked
+            let approval: Vec<bool> = catalog.iter().filter(|c| c.kind == k.kind()).map(|c| c.requires_approval).collect();
+            if approval.first().is_some_and(|first| approval.iter().all(|a| a == first)) {
+                then["properties"]["requires_approval"] = serde_json::json!({ "const": approval[0] });
+            }
+            serde_json::json!({
+                "if": { "properties": { "task_kind": { "const": k.kind() } } },
+                "then": then
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["name", "task_kind", "schedule", "requires_approval"],
+        "properties": {
+            "name": { "type": "string", "minLength": 1, "maxLength": crate::MAX_AGENT_NAME_LEN },
+            "description": { "type": "string" },
+            "task_kind": { "enum": tasks::kinds().iter().map(|k| k.kind()).collect::<Vec<_>>() },
+            "params": { "type": "object" },
+            "schedule": {
+                "type": "string",
+                "description": "5-field cron expression in local time, or one of Manual, Hourly, Daily, Weekly"
+            },
+            "requires_approval": { "type": "boolean" }
+        },
+        "allOf": kinds
+    })
+}
+
+fn prompt(instruction: &str, scripts: &Scripts) -> String {
+    let kinds: Vec<String> = tasks::kinds().iter().map(|k| format!("- {}: {}", k.kind(), k.description())).collect();
+    format!(
+        "You turn plain English instructions into automation agents for a desktop assistant.\n\
+         Available task kinds:\n{}\n\n\
+         Reply with a single JSON object and nothing else. It must match this JSON schema:\n{}\n\n\
+         Instruction: {}",
+        kinds.join("\n"),
+        agent_schema(scripts),
+        instruction
+    )
+}
+
+// Models like to wrap JSON in prose or code fences.
+fn extract_json(reply: &str) -> Option<&str> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    (start < end).then(|| &reply[start..=end])
+}
+
+fn check(reply: &str, scripts: &Scripts) -> Result<AgentPreview, String> {
+    let raw = extract_json(reply).ok_or("The reply does not contain a JSON object")?;
+    let draft: AgentDraft = serde_json::from_str(raw).map_err(|e| format!("The JSON does not match the schema: {}", e))?;
+    let task = serde_json::json!({ "kind": draft.task_kind, "params": draft.params }).to_string();
+    let (name, task, next_run_at) = crate::validate_agent(&draft.name, &task, &draft.schedule)?;
+
+    let kind = tasks::find(&draft.task_kind).ok_or("Unknown task kind")?;
+    let requires_approval = kind.requires_approval(&tasks::TaskSpec::parse(&task)?, scripts);
+    let mut warnings = Vec::new();
+    if draft.requires_approval != requires_approval {
+        warnings.push(if requires_approval {
+            format!("'{}' agents always ask for approval before posting", kind.kind())
+        } else {
+            format!("'{}' agents act without asking for approval", kind.kind())
+        });
+    }
+    if scheduler::parse_schedule(&draft.schedule)?.is_none() {
+        warnings.push("The agent only runs when started by hand".to_string());
+    }
+    Ok(AgentPreview {
+        name,
+        description: draft.description.trim().to_string(),
+        task,
+        schedule: draft.schedule.trim().to_string(),
+        next_run_at,
+        requires_approval,
+        warnings,
+        provider: String::new(),
+    })
+}
+
+// Asks the configured LLM for an agent spec and validates it. Nothing is
+// stored; the caller confirms the preview and passes it to `create_agent`.
+#[tauri::command]
+pub async fn compile_agent(
+    state: State<'_, DbState>,
+    vault: State<'_, Vault>,
+    scripts: State<'_, Scripts>,
+    instruction: String,
+) -> Result<AgentPreview, String> {
+    let instruction = instruction.trim();
+    if instruction.is_empty() {
+        return Err("Describe what the agent should do".into());
+    }
+
+    let mut messages = vec![ChatMessage::user(&prompt(instruction, &scripts))];
+    let mut last_error = String::new();
+    for _ in 0..MAX_ATTEMPTS {
+        let completion = llm::complete(&state, &vault, &messages).await?;
+        match check(&completion.content, &scripts) {
+            Ok(preview) => return Ok(AgentPreview { provider: completion.provider.to_string(), ..preview }),
+            Err(e) => {
+                // Give the model one chance to fix its answer
+                messages.push(ChatMessage { role: "assistant".into(), content: completion.content });
+                messages.push(ChatMessage::user(&format!("That reply is invalid: {}. Reply again with only the corrected JSON object.", e)));
+                last_error = e;
+            }
+        }
+    }
+
+    let error = format!("Could not turn the instruction into an agent: {}", last_error);
+    let conn = state.0.lock().unwrap();
+    let _ = log_event(&conn, None, &error, "Error");
+    Err(error)
+}
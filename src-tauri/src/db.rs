@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rusqlite::{Connection, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
@@ -12,12 +13,28 @@ pub struct Agent {
     pub status: String,
     pub last_run_at: Option<String>,
     pub next_run_at: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub retry_policy: Option<crate::retry::RetryPolicy>,
+    // Failed attempts so far for the current scheduled occurrence
+    pub retry_attempt: i32,
+    pub last_run_status: Option<String>, // Succeeded, Retrying, Failed, GaveUp
+    pub misfire_policy: crate::scheduler::MisfirePolicy,
+    // Runs of this agent allowed at the same time
+    pub max_concurrency: i32,
+    // Scheduled runs go against the mock targets and publish nothing
+    pub dry_run: bool,
+    // Browser session (<site>/<account>) that must be logged into again before the agent can run
+    pub needs_login: Option<String>,
+    pub approval_policy: crate::approvals::ApprovalPolicy,
+    // Missed slot still owed by a misfire catch-up while the current one is retried
+    pub catch_up_at: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Log {
     pub id: i32,
-    pub agent_id: i32,
+    pub agent_id: Option<i32>,
     pub timestamp: String,
     pub message: String,
     pub level: String, // Info, Error
@@ -28,58 +45,112 @@ pub struct Approval {
     pub id: i32,
     pub agent_id: i32,
     pub content: String,
-    pub status: String, // Pending, Approved, Rejected
+    // Pending, Executing, Posted, Failed, Unknown (may have been posted),
+    // Rejected, Expired; Approved on rows decided before posting outcomes were tracked
+    pub status: String,
+    // What the agent produced; `content` is the text after any edits
+    pub draft: String,
+    pub created_at: Option<String>,
+    // Set from the agent's approval policy when the draft is queued
+    pub expires_at: Option<String>,
+    // The newer draft that auto-rejected this one
+    pub superseded_by: Option<i32>,
+    // The latest run posting it, and how many times posting was started
+    pub run_id: Option<i64>,
+    pub attempts: i32,
+    // Why the last posting attempt failed
+    pub error: Option<String>,
+    // Content policy rules the current text breaks; they are why a draft from
+    // a kind that normally publishes directly is waiting here
+    pub policy_violations: Vec<crate::policy::Violation>,
+    #[serde(default)]
+    pub revisions: Vec<crate::approvals::ApprovalRevision>,
 }
 
-#[derive(Clone)]
-pub struct DbState(pub Arc<Mutex<Connection>>);
+// Same layout SQLite uses for CURRENT_TIMESTAMP, so stored times compare as text
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
-pub fn init_db(path: &str) -> Result<Connection> {
-    let conn = Connection::open(path)?;
+pub fn format_timestamp(t: DateTime<Utc>) -> String {
+    t.format(TIMESTAMP_FORMAT).to_string()
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS agents (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            description TEXT,
-            openclaw_task TEXT NOT NULL,
-            schedule TEXT,
-            status TEXT DEFAULT 'Active',
-            last_run_at DATETIME,
-            next_run_at DATETIME
-        )",
-        [],
-    )?;
+pub fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(s, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS logs (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            agent_id INTEGER,
-            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-            message TEXT,
-            level TEXT,
-            FOREIGN KEY(agent_id) REFERENCES agents(id)
-        )",
-        [],
-    )?;
+pub const AGENT_COLUMNS: &str = "id, name, description, openclaw_task, schedule, status, last_run_at, next_run_at, created_at, updated_at, retry_policy, retry_attempt, last_run_status, misfire_policy, max_concurrency, dry_run, needs_login, approval_policy, catch_up_at";
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS settings (
-            key TEXT PRIMARY KEY,
-            value TEXT
-        )",
-        [],
-    )?;
+pub fn agent_from_row(row: &rusqlite::Row) -> Result<Agent> {
+    Ok(Agent {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2).unwrap_or_default(),
+        openclaw_task: row.get(3)?,
+        schedule: row.get(4).unwrap_or_default(),
+        status: row.get(5)?,
+        last_run_at: row.get(6).ok(),
+        next_run_at: row.get(7).ok(),
+        created_at: row.get(8).ok(),
+        updated_at: row.get(9).ok(),
+        retry_policy: row
+            .get::<_, Option<String>>(10)?
+            .and_then(|p| serde_json::from_str(&p).ok()),
+        retry_attempt: row.get(11)?,
+        last_run_status: row.get(12)?,
+        misfire_policy: row
+            .get::<_, Option<String>>(13)?
+            .and_then(|p| serde_json::from_str(&p).ok())
+            .unwrap_or_default(),
+        max_concurrency: row.get(14)?,
+        dry_run: row.get(15)?,
+        needs_login: row.get(16)?,
+        approval_policy: row
+            .get::<_, Option<String>>(17)?
+            .and_then(|p| serde_json::from_str(&p).ok())
+            .unwrap_or_default(),
+        catch_up_at: row.get(18)?,
+    })
+}
 
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS approvals (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            agent_id INTEGER,
-            content TEXT,
-            status TEXT DEFAULT 'Pending'
-        )",
-        [],
-    )?;
+pub fn list_agents(conn: &Connection, status: Option<&str>) -> Result<Vec<Agent>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM agents WHERE ?1 IS NULL OR status = ?1 ORDER BY id",
+        AGENT_COLUMNS
+    ))?;
+    let agent_iter = stmt.query_map([status], agent_from_row)?;
+    agent_iter.collect()
+}
+
+pub fn get_agent(conn: &Connection, id: i32) -> Result<Agent> {
+    conn.query_row(
+        &format!("SELECT {} FROM agents WHERE id = ?", AGENT_COLUMNS),
+        [id],
+        agent_from_row,
+    )
+}
+
+pub fn list_logs(conn: &Connection, limit: u32) -> Result<Vec<Log>> {
+    let mut stmt = conn.prepare("SELECT id, agent_id, timestamp, message, level FROM logs ORDER BY timestamp DESC, id DESC LIMIT ?")?;
+    let log_iter = stmt.query_map([limit], |row| {
+        Ok(Log {
+            id: row.get(0)?,
+            agent_id: row.get(1)?,
+            timestamp: row.get(2)?,
+            message: row.get(3)?,
+            level: row.get(4)?,
+        })
+    })?;
+    log_iter.collect()
+}
+
+#[derive(Clone)]
+pub struct DbState(pub Arc<Mutex<Connection>>);
+
+pub fn init_db(path: &str) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    crate::migrations::run(&mut conn, crate::migrations::legacy_db_path().as_deref())?;
     Ok(conn)
 }
 
@@ -1,117 +1,237 @@
+mod approvals;
 mod db;
+mod migrations;
+mod llm;
 use rusqlite::OptionalExtension;
 mod openclaw;
+mod plugins;
+mod policy;
+mod retry;
+mod runner;
+mod runs;
 mod scheduler;
+mod scripts;
+mod secrets;
+mod sessions;
+mod stream;
+mod tasks;
 mod commands;
+mod compiler;
 
 use commands::{detect_env, install_openclaw, check_ollama, ensure_phi3};
+use tasks::list_task_kinds;
+use compiler::compile_agent;
+use llm::{chat_completion, get_llm_settings, update_llm_settings};
+use secrets::{Vault, get_vault_status, unlock_vault, lock_vault, set_vault_passphrase, list_secrets, set_secret, delete_secret};
+use runs::{list_runs, get_run};
+use policy::{get_content_policy, update_content_policy, check_content};
+use approvals::{ApprovalPolicy, get_approvals, get_approval, edit_approval, approve_request, retry_approval, confirm_approval};
+use commands::{db_init, create_demo_agents, list_agents, run_agent_now, get_logs_legacy};
 
 use std::sync::{Arc, Mutex};
 use tauri::{Manager, State};
-use db::{DbState, Agent, Log, Approval};
-use scheduler::start_scheduler;
+use db::{DbState, Agent, Log};
+use retry::RetryPolicy;
+use runner::{Runner, cancel_run, subscribe_run};
+use plugins::list_automations;
+use scripts::{Scripts, check_scripts};
+use sessions::{Sessions, login_session, list_sessions, delete_session};
+use stream::Streams;
+use scheduler::{start_scheduler, MisfirePolicy};
 
-#[tauri::command]
-fn get_agents(state: State<DbState>) -> std::result::Result<Vec<Agent>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT id, name, description, openclaw_task, schedule, status, last_run_at, next_run_at FROM agents").map_err(|e| e.to_string())?;
-    let agent_iter = stmt.query_map([], |row| {
-        Ok(Agent {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            description: row.get(2).unwrap_or_default(),
-            openclaw_task: row.get(3)?,
-            schedule: row.get(4).unwrap_or_default(),
-            status: row.get(5)?,
-            last_run_at: row.get(6).ok(),
-            next_run_at: row.get(7).ok(),
-        })
-    }).map_err(|e| e.to_string())?;
+const MAX_AGENT_NAME_LEN: usize = 100;
 
-    let mut list = Vec::new();
-    for agent in agent_iter {
-        list.push(agent.map_err(|e| e.to_string())?);
+// Checks user-supplied fields and returns the trimmed name, canonical task spec
+// and the first `next_run_at` for the schedule.
+fn validate_agent(name: &str, task: &str, schedule: &str) -> std::result::Result<(String, String, Option<String>), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Agent name must not be empty".into());
     }
-    Ok(list)
+    if name.chars().count() > MAX_AGENT_NAME_LEN {
+        return Err(format!("Agent name must be at most {} characters", MAX_AGENT_NAME_LEN));
+    }
+    let task = tasks::validate(task)?;
+    let next_run_at = scheduler::initial_next_run(schedule)?;
+    Ok((name.to_string(), task, next_run_at))
 }
 
-#[tauri::command]
-fn create_agent(state: State<DbState>, name: String, task: String, schedule: String) -> std::result::Result<(), String> {
+// Moves an agent to `to` if its current status is one of `from`.
+fn transition_agent(state: &DbState, id: i32, from: &[&str], to: &str, next_run_at: Option<String>) -> std::result::Result<(), String> {
     let conn = state.0.lock().unwrap();
+    let agent = db::get_agent(&conn, id).map_err(|e| e.to_string())?;
+    if !from.contains(&agent.status.as_str()) {
+        return Err(format!("Agent '{}' is {} and cannot become {}", agent.name, agent.status, to));
+    }
     conn.execute(
-        "INSERT INTO agents (name, openclaw_task, schedule) VALUES (?, ?, ?)",
-        [name, task, schedule],
+        // A status change abandons any retries pending for the current occurrence
+        "UPDATE agents SET status = ?, next_run_at = ?, retry_attempt = 0, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![to, next_run_at, id],
     ).map_err(|e| e.to_string())?;
+    let _ = db::log_event(&conn, Some(id), &format!("Agent '{}' changed from {} to {}", agent.name, agent.status, to), "Info");
     Ok(())
 }
 
 #[tauri::command]
-fn get_logs(state: State<DbState>) -> std::result::Result<Vec<Log>, String> {
+fn get_agents(state: State<DbState>, include_archived: Option<bool>) -> std::result::Result<Vec<Agent>, String> {
     let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT id, agent_id, timestamp, message, level FROM logs ORDER BY timestamp DESC LIMIT 50").map_err(|e| e.to_string())?;
-    let log_iter = stmt.query_map([], |row| {
-        Ok(Log {
-            id: row.get(0)?,
-            agent_id: row.get(1)?,
-            timestamp: row.get(2)?,
-            message: row.get(3)?,
-            level: row.get(4)?,
-        })
-    }).map_err(|e| e.to_string())?;
-
-    let mut list = Vec::new();
-    for log in log_iter {
-        list.push(log.map_err(|e| e.to_string())?);
-    }
-    Ok(list)
+    let agents = db::list_agents(&conn, None).map_err(|e| e.to_string())?;
+    let include_archived = include_archived.unwrap_or(false);
+    Ok(agents.into_iter().filter(|a| include_archived || a.status != "Archived").collect())
 }
 
 #[tauri::command]
-fn get_approvals(state: State<DbState>) -> std::result::Result<Vec<Approval>, String> {
+fn create_agent(state: State<DbState>, name: String, task: String, schedule: String, description: Option<String>) -> std::result::Result<i64, String> {
+    let (name, task, next_run_at) = validate_agent(&name, &task, &schedule)?;
+
     let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT id, agent_id, content, status FROM approvals WHERE status = 'Pending'").map_err(|e| e.to_string())?;
-    let app_iter = stmt.query_map([], |row| {
-        Ok(Approval {
-            id: row.get(0)?,
-            agent_id: row.get(1)?,
-            content: row.get(2)?,
-            status: row.get(3)?,
-        })
-    }).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agents (name, description, openclaw_task, schedule, next_run_at, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)",
+        rusqlite::params![name, description, task, schedule, next_run_at],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+fn update_agent(state: State<DbState>, id: i32, name: String, task: String, schedule: String, description: Option<String>) -> std::result::Result<(), String> {
+    let (name, task, next_run_at) = validate_agent(&name, &task, &schedule)?;
 
-    let mut list = Vec::new();
-    for app in app_iter {
-        list.push(app.map_err(|e| e.to_string())?);
+    let conn = state.0.lock().unwrap();
+    let agent = db::get_agent(&conn, id).map_err(|e| e.to_string())?;
+    if agent.status == "Archived" {
+        return Err(format!("Agent '{}' is archived and cannot be edited", agent.name));
     }
-    Ok(list)
+    // Only active agents carry a pending run; paused ones get one on resume
+    let next_run_at = if agent.status == "Active" { next_run_at } else { None };
+    conn.execute(
+        "UPDATE agents SET name = ?, description = ?, openclaw_task = ?, schedule = ?, next_run_at = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        rusqlite::params![name, description, task, schedule, next_run_at, id],
+    ).map_err(|e| e.to_string())?;
+    let _ = db::log_event(&conn, Some(id), &format!("Agent '{}' updated", name), "Info");
+    Ok(())
 }
 
 #[tauri::command]
-fn approve_request(state: State<DbState>, id: i32, approved: bool) -> std::result::Result<(), String> {
-    let (content, current_status) = {
+fn pause_agent(state: State<DbState>, id: i32) -> std::result::Result<(), String> {
+    transition_agent(&state, id, &["Active", "Error"], "Paused", None)
+}
+
+#[tauri::command]
+fn resume_agent(state: State<DbState>, id: i32) -> std::result::Result<(), String> {
+    let schedule = {
         let conn = state.0.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT content, status FROM approvals WHERE id = ?").map_err(|e| e.to_string())?;
-        stmt.query_row([id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))).map_err(|e| e.to_string())?
+        db::get_agent(&conn, id).map_err(|e| e.to_string())?.schedule
     };
+    // Re-validated here so an agent disabled for a bad schedule can't be resumed as-is
+    let next_run_at = scheduler::initial_next_run(&schedule)?;
+    transition_agent(&state, id, &["Paused", "Error"], "Active", next_run_at)
+}
 
-    if approved && current_status == "Pending" {
-        // Run the script
-        {
-            let conn = state.0.lock().unwrap();
-            let _ = db::log_event(&conn, None, &format!("Triggering LinkedIn post for content: {}", content), "Info");
-        }
-        crate::openclaw::run_script("linkedin_post.cjs", &content);
-    }
+#[tauri::command]
+fn archive_agent(state: State<DbState>, id: i32) -> std::result::Result<(), String> {
+    transition_agent(&state, id, &["Active", "Paused", "Error"], "Archived", None)
+}
 
-    let status_set = if approved { "Approved" } else { "Rejected" };
+#[tauri::command]
+fn duplicate_agent(state: State<DbState>, id: i32) -> std::result::Result<i64, String> {
     let conn = state.0.lock().unwrap();
+    let agent = db::get_agent(&conn, id).map_err(|e| e.to_string())?;
+    let name: String = format!("{} (copy)", agent.name).chars().take(MAX_AGENT_NAME_LEN).collect();
+    // Copies start paused so a duplicate never fires alongside its original by surprise
     conn.execute(
-        "UPDATE approvals SET status = ? WHERE id = ?",
-        rusqlite::params![status_set, id],
+        "INSERT INTO agents (name, description, openclaw_task, schedule, retry_policy, misfire_policy, max_concurrency, dry_run, needs_login, approval_policy, status, created_at, updated_at)
+         SELECT ?, description, openclaw_task, schedule, retry_policy, misfire_policy, max_concurrency, dry_run, needs_login, approval_policy, 'Paused', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+         FROM agents WHERE id = ?",
+        rusqlite::params![name, id],
+    ).map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Sets one agent setting. `column` is always a literal from the commands below.
+fn update_agent_column(state: &DbState, id: i32, column: &'static str, value: impl rusqlite::ToSql) -> std::result::Result<(), String> {
+    let conn = state.0.lock().unwrap();
+    let updated = conn.execute(
+        &format!("UPDATE agents SET {} = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?", column),
+        rusqlite::params![value, id],
     ).map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Agent {} not found", id));
+    }
+    Ok(())
+}
+
+// `None` restores the default policy.
+#[tauri::command]
+fn update_retry_policy(state: State<DbState>, id: i32, policy: Option<RetryPolicy>) -> std::result::Result<(), String> {
+    let raw = match policy {
+        Some(policy) => {
+            policy.validate()?;
+            Some(serde_json::to_string(&policy).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+    update_agent_column(&state, id, "retry_policy", raw)
+}
+
+#[tauri::command]
+fn update_approval_policy(state: State<DbState>, id: i32, policy: ApprovalPolicy) -> std::result::Result<(), String> {
+    policy.validate()?;
+    let raw = serde_json::to_string(&policy).map_err(|e| e.to_string())?;
+    update_agent_column(&state, id, "approval_policy", raw)
+}
+
+#[tauri::command]
+fn update_misfire_policy(state: State<DbState>, id: i32, policy: MisfirePolicy) -> std::result::Result<(), String> {
+    policy.validate()?;
+    let raw = serde_json::to_string(&policy).map_err(|e| e.to_string())?;
+    update_agent_column(&state, id, "misfire_policy", raw)
+}
+
+#[tauri::command]
+fn update_agent_concurrency(state: State<DbState>, id: i32, max_concurrency: u32) -> std::result::Result<(), String> {
+    if max_concurrency == 0 {
+        return Err("max_concurrency must be at least 1".into());
+    }
+    update_agent_column(&state, id, "max_concurrency", max_concurrency)
+}
+
+// While on, the scheduler fires the agent as dry runs.
+#[tauri::command]
+fn update_agent_dry_run(state: State<DbState>, id: i32, enabled: bool) -> std::result::Result<(), String> {
+    update_agent_column(&state, id, "dry_run", enabled)
+}
+
+#[tauri::command]
+fn delete_agent(state: State<DbState>, id: i32) -> std::result::Result<(), String> {
+    let mut conn = state.0.lock().unwrap();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM logs WHERE agent_id = ?", [id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM approval_revisions WHERE approval_id IN (SELECT id FROM approvals WHERE agent_id = ?)", [id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM publications WHERE approval_id IN (SELECT id FROM approvals WHERE agent_id = ?)", [id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM approvals WHERE agent_id = ?", [id]).map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM runs WHERE agent_id = ?", [id]).map_err(|e| e.to_string())?;
+    // What the agent published stays public, so it still counts for the duplicate check
+    tx.execute("UPDATE published_content SET agent_id = NULL WHERE agent_id = ?", [id]).map_err(|e| e.to_string())?;
+    let deleted = tx.execute("DELETE FROM agents WHERE id = ?", [id]).map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Agent {} does not exist", id));
+    }
+    tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[tauri::command]
+async fn trigger_agent(runner: State<'_, Runner>, id: i32, dry_run: bool) -> std::result::Result<String, String> {
+    runner.run_agent_by_id(id, dry_run).await
+}
+
+#[tauri::command]
+fn get_logs(state: State<DbState>) -> std::result::Result<Vec<Log>, String> {
+    let conn = state.0.lock().unwrap();
+    db::list_logs(&conn, 50).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn log_event_cmd(state: State<DbState>, agent_id: Option<i32>, message: String, level: String) -> std::result::Result<(), String> {
     let conn = state.0.lock().unwrap();
@@ -120,20 +240,26 @@ fn log_event_cmd(state: State<DbState>, agent_id: Option<i32>, message: String,
 }
 
 #[tauri::command]
-fn get_llm_settings(state: State<DbState>) -> std::result::Result<Option<String>, String> {
+fn get_max_concurrent_runs(state: State<DbState>) -> std::result::Result<u32, String> {
     let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = 'llm_api_key'").map_err(|e| e.to_string())?;
-    let res = stmt.query_row([], |row| row.get::<_, String>(0)).optional().map_err(|e: rusqlite::Error| e.to_string())?;
-    Ok(res)
+    let value = conn
+        .query_row("SELECT value FROM settings WHERE key = 'max_concurrent_runs'", [], |row| row.get::<_, String>(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    Ok(value.and_then(|v| v.parse().ok()).unwrap_or(runner::DEFAULT_MAX_CONCURRENT_RUNS as u32))
 }
 
 #[tauri::command]
-fn update_llm_settings(state: State<DbState>, key: String) -> std::result::Result<(), String> {
+fn update_max_concurrent_runs(state: State<DbState>, runner: State<Runner>, limit: u32) -> std::result::Result<(), String> {
+    if limit == 0 {
+        return Err("At least one run must be allowed at a time".into());
+    }
     let conn = state.0.lock().unwrap();
     conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value) VALUES ('llm_api_key', ?)",
-        [key],
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('max_concurrent_runs', ?)",
+        [limit.to_string()],
     ).map_err(|e| e.to_string())?;
+    runner.set_pool_size(limit as usize);
     Ok(())
 }
 
@@ -149,30 +275,91 @@ pub fn run() {
             let db_path = data_dir.join("personaliz.db");
             
             let conn = db::init_db(db_path.to_str().expect("invalid path")).expect("failed to init db");
+            let vault = Vault::open(&conn, data_dir.join("vault.key")).expect("failed to open secrets vault");
+            if let Ok(interrupted @ 1..) = approvals::recover_interrupted(&conn) {
+                let _ = db::log_event(&conn, None, &format!("{} approval(s) were still posting when the app closed; check whether they went out", interrupted), "Error");
+            }
             let db_state = DbState(Arc::new(Mutex::new(conn)));
             app.manage(db_state.clone());
+            app.manage(vault.clone());
+            let resource_dir = app_handle.path().resource_dir().expect("failed to get resource dir");
+            let scripts = Scripts::new(resource_dir.join("scripts"), data_dir.join("scripts"));
+            std::fs::create_dir_all(&scripts.overrides).expect("failed to create scripts dir");
+            app.manage(scripts.clone());
+            let sessions = Sessions::new(data_dir.join("sessions"));
+            app.manage(sessions.clone());
+            let runner = Runner::new(db_state.clone(), vault, scripts.clone(), sessions, Streams::new(Some(app_handle.clone())), data_dir.join("published_keys"));
+            app.manage(runner.clone());
+
+            // Report missing scripts or runtimes before an agent trips over them
+            let startup_db = db_state.clone();
+            tauri::async_runtime::spawn_blocking(move || scripts::check_on_startup(&scripts, &startup_db));
 
             // Start scheduler
-            let scheduler_state = db_state.clone();
             tauri::async_runtime::spawn(async move {
-                start_scheduler(scheduler_state).await;
+                start_scheduler(runner).await;
             });
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_agents,
+            compile_agent,
             create_agent,
+            update_agent,
+            pause_agent,
+            resume_agent,
+            archive_agent,
+            duplicate_agent,
+            update_retry_policy,
+            update_misfire_policy,
+            update_approval_policy,
+            update_agent_concurrency,
+            update_agent_dry_run,
+            delete_agent,
+            trigger_agent,
+            list_task_kinds,
+            list_runs,
+            get_run,
+            cancel_run,
+            subscribe_run,
+            check_scripts,
+            list_automations,
+            login_session,
+            list_sessions,
+            delete_session,
             get_logs,
             get_approvals,
+            get_approval,
+            edit_approval,
             approve_request,
+            retry_approval,
+            confirm_approval,
+            get_content_policy,
+            update_content_policy,
+            check_content,
             log_event_cmd,
             get_llm_settings,
             update_llm_settings,
+            chat_completion,
+            get_vault_status,
+            unlock_vault,
+            lock_vault,
+            set_vault_passphrase,
+            list_secrets,
+            set_secret,
+            delete_secret,
+            get_max_concurrent_runs,
+            update_max_concurrent_runs,
             detect_env,
             install_openclaw,
             check_ollama,
-            ensure_phi3
+            ensure_phi3,
+            db_init,
+            create_demo_agents,
+            list_agents,
+            run_agent_now,
+            get_logs_legacy
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
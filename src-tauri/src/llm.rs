@@ -0,0 +1,297 @@
+use crate::db::{DbState, log_event};
+use crate::secrets::{self, Vault};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tauri::State;
+use tokio::sync::watch;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-3.5-turbo";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+const DEFAULT_OLLAMA_MODEL: &str = "phi3";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChatMessage {
+    pub role: String, // system, user, assistant
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn user(content: &str) -> Self {
+        ChatMessage { role: "user".into(), content: content.into() }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Completion {
+    pub provider: &'static str,
+    pub model: String,
+    pub content: String,
+}
+
+pub type ChatFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+pub trait LlmProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn model(&self) -> &str;
+    fn chat<'a>(&'a self, client: &'a reqwest::Client, messages: &'a [ChatMessage]) -> ChatFuture<'a>;
+}
+
+pub struct Ollama {
+    pub base_url: String,
+    pub model: String,
+}
+
+impl LlmProvider for Ollama {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn chat<'a>(&'a self, client: &'a reqwest::Client, messages: &'a [ChatMessage]) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let res = client
+                .post(format!("{}/api/chat", self.base_url.trim_end_matches('/')))
+                .json(&serde_json::json!({ "model": self.model, "messages": messages, "stream": false }))
+                .send()
+                .await
+                .map_err(|e| format!("Ollama is not reachable: {}", e))?;
+            let body = read_json(res).await?;
+            body.pointer("/message/content")
+                .and_then(|c| c.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| format!("Unexpected Ollama response: {}", body))
+        })
+    }
+}
+
+// Any server speaking the OpenAI chat completions API.
+pub struct OpenAiCompatible {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn chat<'a>(&'a self, client: &'a reqwest::Client, messages: &'a [ChatMessage]) -> ChatFuture<'a> {
+        Box::pin(async move {
+            let res = client
+                .post(format!("{}/chat/completions", self.base_url.trim_end_matches('/')))
+                .bearer_auth(&self.api_key)
+                .json(&serde_json::json!({ "model": self.model, "messages": messages }))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let body = read_json(res).await?;
+            body.pointer("/choices/0/message/content")
+                .and_then(|c| c.as_str())
+                .map(str::to_string)
+                .ok_or_else(|| format!("Unexpected completion response: {}", body))
+        })
+    }
+}
+
+async fn read_json(res: reqwest::Response) -> Result<serde_json::Value, String> {
+    let status = res.status();
+    let body: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        let message = body.pointer("/error/message").or_else(|| body.get("error")).unwrap_or(&body);
+        return Err(format!("HTTP {}: {}", status.as_u16(), message));
+    }
+    Ok(body)
+}
+
+// LLM configuration kept in the `settings` table; the API key itself lives in
+// the secrets vault under the "openai" provider. It never leaves the backend;
+// the webview only learns whether one is set.
+#[derive(Serialize, Debug, Clone)]
+pub struct LlmSettings {
+    // auto (external when a key is set, falling back to Ollama), openai or ollama
+    pub provider: String,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    // Which of the "openai" secrets to use
+    pub secret: String,
+    pub has_api_key: bool,
+    pub base_url: String,
+    pub model: String,
+    pub ollama_url: String,
+    pub ollama_model: String,
+}
+
+fn setting(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?", [key], |row| row.get::<_, String>(0))
+        .optional()
+        .map(|v| v.filter(|v| !v.trim().is_empty()))
+}
+
+impl LlmSettings {
+    // Without a vault (or with a locked one) the key is reported but not loaded.
+    pub fn load(conn: &Connection, vault: Option<&Vault>) -> Result<Self, String> {
+        let secret = setting(conn, "llm_secret").map_err(|e| e.to_string())?.unwrap_or_else(|| "default".into());
+        let has_api_key = secrets::exists(conn, "openai", &secret).map_err(|e| e.to_string())?;
+        let api_key = match vault {
+            Some(vault) if has_api_key && vault.is_unlocked() => vault.get(conn, "openai", &secret)?,
+            _ => None,
+        };
+        Self::with_key(conn, secret, has_api_key, api_key).map_err(|e| e.to_string())
+    }
+
+    fn with_key(conn: &Connection, secret: String, has_api_key: bool, api_key: Option<String>) -> rusqlite::Result<Self> {
+        Ok(LlmSettings {
+            provider: setting(conn, "llm_provider")?.unwrap_or_else(|| "auto".into()),
+            secret,
+            has_api_key,
+            api_key,
+            base_url: setting(conn, "llm_base_url")?.unwrap_or_else(|| DEFAULT_OPENAI_BASE_URL.into()),
+            model: setting(conn, "llm_model")?.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.into()),
+            ollama_url: setting(conn, "ollama_url")?.unwrap_or_else(|| DEFAULT_OLLAMA_URL.into()),
+            ollama_model: setting(conn, "ollama_model")?.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.into()),
+        })
+    }
+
+    // Providers in the order they are tried.
+    pub fn providers(&self) -> Result<Vec<Box<dyn LlmProvider>>, String> {
+        let external = self.api_key.clone().map(|api_key| {
+            Box::new(OpenAiCompatible { base_url: self.base_url.clone(), api_key, model: self.model.clone() }) as Box<dyn LlmProvider>
+        });
+        let local = Box::new(Ollama { base_url: self.ollama_url.clone(), model: self.ollama_model.clone() }) as Box<dyn LlmProvider>;
+        match self.provider.as_str() {
+            "auto" => Ok(external.into_iter().chain(std::iter::once(local)).collect()),
+            "openai" => external.map(|p| vec![p]).ok_or_else(|| {
+                if self.has_api_key {
+                    "The secrets vault is locked; unlock it to use the external LLM".to_string()
+                } else {
+                    "No API key configured for the external LLM".to_string()
+                }
+            }),
+            "ollama" => Ok(vec![local]),
+            other => Err(format!("Unknown LLM provider '{}' (expected auto, openai or ollama)", other)),
+        }
+    }
+}
+
+// Tries each configured provider in turn and returns the first answer.
+pub async fn complete(state: &DbState, vault: &Vault, messages: &[ChatMessage]) -> Result<Completion, String> {
+    let settings = {
+        let conn = state.0.lock().unwrap();
+        LlmSettings::load(&conn, Some(vault))?
+    };
+    let providers = settings.providers()?;
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().map_err(|e| e.to_string())?;
+
+    let mut errors = Vec::new();
+    for provider in &providers {
+        match provider.chat(&client, messages).await {
+            Ok(content) => {
+                if !errors.is_empty() {
+                    let conn = state.0.lock().unwrap();
+                    let _ = log_event(&conn, None, &format!("LLM fell back to {}: {}", provider.name(), errors.join("; ")), "Info");
+                }
+                return Ok(Completion { provider: provider.name(), model: provider.model().to_string(), content });
+            }
+            Err(e) => errors.push(format!("{} failed: {}", provider.name(), e)),
+        }
+    }
+    let error = format!("No LLM provider answered. {}", errors.join("; "));
+    let conn = state.0.lock().unwrap();
+    let _ = log_event(&conn, None, &error, "Error");
+    Err(error)
+}
+
+// `complete` for task code on the blocking pool. It gives up when the run's
+// timeout passes or the run is cancelled, whichever comes first.
+pub fn complete_blocking(
+    state: &DbState,
+    vault: &Vault,
+    messages: &[ChatMessage],
+    timeout: Duration,
+    mut cancel: watch::Receiver<bool>,
+) -> Result<Completion, String> {
+    tauri::async_runtime::block_on(async {
+        tokio::select! {
+            completion = tokio::time::timeout(timeout, complete(state, vault, messages)) => {
+                completion.unwrap_or_else(|_| Err(format!("The LLM did not answer within {}s", timeout.as_secs())))
+            }
+            _ = crate::openclaw::cancelled(&mut cancel) => Err("Cancelled".to_string()),
+        }
+    })
+}
+
+#[tauri::command]
+pub async fn chat_completion(state: State<'_, DbState>, vault: State<'_, Vault>, messages: Vec<ChatMessage>) -> Result<Completion, String> {
+    if messages.is_empty() {
+        return Err("At least one message is required".into());
+    }
+    complete(&state, &vault, &messages).await
+}
+
+#[tauri::command]
+pub fn get_llm_settings(state: State<DbState>) -> Result<LlmSettings, String> {
+    let conn = state.0.lock().unwrap();
+    LlmSettings::load(&conn, None)
+}
+
+// Fields left out are unchanged. `key` is stored in the vault under the
+// selected secret name; an empty `key` deletes it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_llm_settings(
+    state: State<DbState>,
+    vault: State<Vault>,
+    key: Option<String>,
+    secret: Option<String>,
+    provider: Option<String>,
+    base_url: Option<String>,
+    model: Option<String>,
+    ollama_url: Option<String>,
+    ollama_model: Option<String>,
+) -> Result<(), String> {
+    if let Some(p) = provider.as_deref() {
+        if !["auto", "openai", "ollama"].contains(&p) {
+            return Err(format!("Unknown LLM provider '{}' (expected auto, openai or ollama)", p));
+        }
+    }
+    let conn = state.0.lock().unwrap();
+    let updates = [
+        ("llm_secret", secret),
+        ("llm_provider", provider),
+        ("llm_base_url", base_url),
+        ("llm_model", model),
+        ("ollama_url", ollama_url),
+        ("ollama_model", ollama_model),
+    ];
+    for (name, value) in updates {
+        if let Some(value) = value {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+                [name, value.trim()],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+    if let Some(key) = key {
+        let name = setting(&conn, "llm_secret").map_err(|e| e.to_string())?.unwrap_or_else(|| "default".into());
+        if key.trim().is_empty() {
+            conn.execute("DELETE FROM secrets WHERE provider = 'openai' AND name = ?", [&name])
+                .map_err(|e| e.to_string())?;
+        } else {
+            vault.put(&conn, "openai", &name, key.trim())?;
+        }
+    }
+    Ok(())
+}
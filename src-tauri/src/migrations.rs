@@ -0,0 +1,624 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+// Append only: a released migration must never be edited or reordered, since
+// `PRAGMA user_version` records how far each user's database has been upgraded.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "initial schema", up: initial_schema },
+    Migration { version: 2, name: "fold legacy command tables", up: fold_legacy_tables },
+    Migration { version: 3, name: "structured task specs", up: structured_task_specs },
+    Migration { version: 4, name: "agent audit timestamps", up: agent_audit_timestamps },
+    Migration { version: 5, name: "run history", up: run_history },
+    Migration { version: 6, name: "retry policies", up: retry_policies },
+    Migration { version: 7, name: "misfire policies", up: misfire_policies },
+    Migration { version: 8, name: "agent concurrency", up: agent_concurrency },
+    Migration { version: 9, name: "secrets vault", up: secrets_vault },
+    Migration { version: 10, name: "dry runs", up: dry_runs },
+    Migration { version: 11, name: "browser sessions", up: browser_sessions },
+    Migration { version: 12, name: "approval revisions", up: approval_revisions },
+    Migration { version: 13, name: "approval policies", up: approval_policies },
+    Migration { version: 14, name: "approval execution", up: approval_execution },
+    Migration { version: 15, name: "publications", up: publications },
+    Migration { version: 16, name: "content policy", up: content_policy },
+    Migration { version: 17, name: "catch-up retries", up: catch_up_retries },
+];
+
+const LEGACY_SCHEMA: &str = "legacy";
+
+// Where the old `commands.rs` stack kept its own copy of the data.
+pub fn legacy_db_path() -> Option<PathBuf> {
+    let mut p = dirs::data_local_dir()?;
+    p.push("personaliz-desktop");
+    p.push("personaliz.db");
+    Some(p)
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+pub fn run(conn: &mut Connection, legacy_db: Option<&Path>) -> Result<()> {
+    let current = schema_version(conn)?;
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    // ATTACH is not allowed inside a transaction, so do it once up front
+    let attached = match legacy_db {
+        Some(path) if path.exists() && !same_file(conn, path) => {
+            conn.execute("ATTACH DATABASE ? AS legacy", [path.to_string_lossy()])?;
+            true
+        }
+        _ => false,
+    };
+
+    let result = apply(conn, &pending);
+
+    if attached {
+        conn.execute("DETACH DATABASE legacy", [])?;
+    }
+    result
+}
+
+fn apply(conn: &mut Connection, pending: &[&Migration]) -> Result<()> {
+    for migration in pending {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        crate::db::log_event(
+            &tx,
+            None,
+            &format!("Applied database migration {}: {}", migration.version, migration.name),
+            "Info",
+        )?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn same_file(conn: &Connection, path: &Path) -> bool {
+    let main = match conn.path() {
+        Some(p) if !p.is_empty() => PathBuf::from(p),
+        _ => return false,
+    };
+    match (main.canonicalize(), path.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn table_exists(conn: &Connection, schema: &str, table: &str) -> Result<bool> {
+    let sql = format!("SELECT 1 FROM {}.sqlite_master WHERE type = 'table' AND name = ?", schema);
+    Ok(conn.query_row(&sql, [table], |_| Ok(())).optional()?.is_some())
+}
+
+fn has_column(conn: &Connection, schema: &str, table: &str, column: &str) -> Result<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM pragma_table_info(?, ?) WHERE name = ?",
+            params![table, schema, column],
+            |_| Ok(()),
+        )
+        .optional()?;
+    Ok(found.is_some())
+}
+
+fn initial_schema(conn: &Connection) -> Result<()> {
+    // IF NOT EXISTS keeps this a no-op for databases created before migrations existed
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS agents (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            description TEXT,
+            openclaw_task TEXT NOT NULL,
+            schedule TEXT,
+            status TEXT DEFAULT 'Active',
+            last_run_at DATETIME,
+            next_run_at DATETIME
+        );
+        CREATE TABLE IF NOT EXISTS logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            message TEXT,
+            level TEXT,
+            FOREIGN KEY(agent_id) REFERENCES agents(id)
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        );
+        CREATE TABLE IF NOT EXISTS approvals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER,
+            content TEXT,
+            status TEXT DEFAULT 'Pending'
+        );",
+    )
+}
+
+// Copies agents, schedules, logs and settings written by `commands::db_init`
+// (TEXT uuid ids, `config_json`, `schedules`, rfc3339 `ts`) into the main schema.
+fn fold_legacy_tables(conn: &Connection) -> Result<()> {
+    let attached: bool = conn
+        .query_row(
+            "SELECT 1 FROM pragma_database_list WHERE name = ?",
+            [LEGACY_SCHEMA],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !attached {
+        return Ok(());
+    }
+
+    let mut agent_ids: HashMap<String, i64> = HashMap::new();
+
+    if table_exists(conn, LEGACY_SCHEMA, "agents")? && has_column(conn, LEGACY_SCHEMA, "agents", "config_json")? {
+        let has_schedules = table_exists(conn, LEGACY_SCHEMA, "schedules")?;
+        let mut stmt = conn.prepare("SELECT id, name, description, config_json FROM legacy.agents")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (legacy_id, name, description, config_json) = row?;
+            let schedule: Option<String> = if has_schedules {
+                conn.query_row(
+                    "SELECT cron_expr FROM legacy.schedules WHERE agent_id = ? LIMIT 1",
+                    [&legacy_id],
+                    |r| r.get(0),
+                )
+                .optional()?
+            } else {
+                None
+            };
+
+            // The whole config is kept as the task so nothing about the agent is lost.
+            // Imported agents start Paused so nothing posts before the user has looked.
+            conn.execute(
+                "INSERT INTO agents (name, description, openclaw_task, schedule, status) VALUES (?, ?, ?, ?, 'Paused')",
+                params![
+                    name.unwrap_or_else(|| "Imported agent".to_string()),
+                    description,
+                    config_json.unwrap_or_else(|| "{}".to_string()),
+                    schedule,
+                ],
+            )?;
+            agent_ids.insert(legacy_id, conn.last_insert_rowid());
+        }
+    }
+
+    if table_exists(conn, LEGACY_SCHEMA, "logs")? && has_column(conn, LEGACY_SCHEMA, "logs", "ts")? {
+        let mut stmt = conn.prepare("SELECT agent_id, level, message, ts FROM legacy.logs")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (legacy_agent, level, message, ts) = row?;
+            let agent_id = legacy_agent.and_then(|id| agent_ids.get(&id).copied());
+            let level = match level.as_deref().map(str::to_uppercase).as_deref() {
+                Some("ERROR") => "Error",
+                _ => "Info",
+            };
+            let timestamp = ts
+                .as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| crate::db::format_timestamp(t.with_timezone(&chrono::Utc)))
+                .unwrap_or_else(|| crate::db::format_timestamp(chrono::Utc::now()));
+            conn.execute(
+                "INSERT INTO logs (agent_id, timestamp, message, level) VALUES (?, ?, ?, ?)",
+                params![agent_id, timestamp, message, level],
+            )?;
+        }
+    }
+
+    if table_exists(conn, LEGACY_SCHEMA, "settings")? {
+        conn.execute(
+            "INSERT OR IGNORE INTO settings (key, value) SELECT key, value FROM legacy.settings",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+// Rewrites free-text and legacy `config_json` tasks into `tasks::TaskSpec` JSON.
+// Tasks that can't be recognised are left alone and fail loudly when run.
+// The spec check and the inference below are frozen copies of what `tasks` did
+// when this migration shipped, so later task kinds and parameters cannot change
+// what it does to old data.
+fn structured_task_specs(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, name, openclaw_task FROM agents")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, name, task) in rows {
+        if is_v3_task_spec(&task) {
+            continue;
+        }
+        if let Some(kind) = infer_v3_kind(&name, &task) {
+            let json = serde_json::json!({ "kind": kind, "params": {} }).to_string();
+            conn.execute("UPDATE agents SET openclaw_task = ? WHERE id = ?", params![json, id])?;
+        }
+    }
+    Ok(())
+}
+
+// The task kinds that existed at v3, with the parameters each accepted;
+// `hashtag` was required but defaulted, so only a blank value was rejected.
+fn is_v3_task_spec(task: &str) -> bool {
+    let Ok(serde_json::Value::Object(spec)) = serde_json::from_str::<serde_json::Value>(task) else {
+        return false;
+    };
+    let params = match spec.get("params") {
+        None => serde_json::Map::new(),
+        Some(serde_json::Value::Object(params)) => params.clone(),
+        Some(_) => return false,
+    };
+    let accepted: &[&str] = match spec.get("kind").and_then(|k| k.as_str()) {
+        Some("trending_post") => &[],
+        Some("hashtag_comment") => &["hashtag"],
+        _ => return false,
+    };
+    if params.keys().any(|k| !accepted.contains(&k.as_str())) {
+        return false;
+    }
+    !matches!(params.get("hashtag"), Some(serde_json::Value::String(h)) if h.trim().is_empty())
+}
+
+fn infer_v3_kind(name: &str, task: &str) -> Option<&'static str> {
+    let script = serde_json::from_str::<serde_json::Value>(task)
+        .ok()
+        .and_then(|v| v.pointer("/automation/script").and_then(|s| s.as_str()).map(str::to_string));
+    match script.as_deref() {
+        Some("linkedin_post") => Some("trending_post"),
+        Some("linkedin_comment") => Some("hashtag_comment"),
+        _ if name.contains("Trending") => Some("trending_post"),
+        _ if name.contains("Hashtag") => Some("hashtag_comment"),
+        _ => None,
+    }
+}
+
+fn agent_audit_timestamps(conn: &Connection) -> Result<()> {
+    // ADD COLUMN can't take a CURRENT_TIMESTAMP default, so inserts set these explicitly
+    conn.execute_batch(
+        "ALTER TABLE agents ADD COLUMN created_at DATETIME;
+        ALTER TABLE agents ADD COLUMN updated_at DATETIME;
+        UPDATE agents SET created_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP;",
+    )
+}
+
+fn run_history(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER NOT NULL,
+            trigger TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'Running',
+            started_at DATETIME NOT NULL,
+            finished_at DATETIME,
+            exit_code INTEGER,
+            summary TEXT,
+            stdout TEXT,
+            stderr TEXT,
+            result_json TEXT,
+            FOREIGN KEY(agent_id) REFERENCES agents(id)
+        );
+        CREATE INDEX idx_runs_agent_started ON runs (agent_id, started_at);",
+    )
+}
+
+fn retry_policies(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE agents ADD COLUMN retry_policy TEXT;
+        ALTER TABLE agents ADD COLUMN retry_attempt INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE agents ADD COLUMN last_run_status TEXT;
+        ALTER TABLE runs ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;",
+    )
+}
+
+fn misfire_policies(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE agents ADD COLUMN misfire_policy TEXT;")
+}
+
+fn agent_concurrency(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE agents ADD COLUMN max_concurrency INTEGER NOT NULL DEFAULT 1;")
+}
+
+// The plaintext `settings.llm_api_key` is moved in by `secrets::Vault` once a
+// key is available, which migrations do not have.
+fn secrets_vault(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE secrets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            name TEXT NOT NULL,
+            nonce BLOB NOT NULL,
+            ciphertext BLOB NOT NULL,
+            created_at DATETIME NOT NULL,
+            rotated_at DATETIME,
+            UNIQUE (provider, name)
+        );",
+    )
+}
+
+fn dry_runs(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE agents ADD COLUMN dry_run INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE runs ADD COLUMN dry_run INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE runs ADD COLUMN captured_json TEXT;",
+    )
+}
+
+// The sessions themselves are vault secrets; agents only remember which one
+// they are waiting on.
+fn browser_sessions(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE agents ADD COLUMN needs_login TEXT;")
+}
+
+// `approvals.content` becomes the text that will be posted and follows each
+// edit; `draft` keeps what the agent originally produced.
+fn approval_revisions(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE approvals ADD COLUMN draft TEXT;
+        ALTER TABLE approvals ADD COLUMN created_at DATETIME;
+        UPDATE approvals SET draft = content, created_at = CURRENT_TIMESTAMP;
+        CREATE TABLE approval_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            approval_id INTEGER NOT NULL,
+            revision INTEGER NOT NULL,
+            author TEXT NOT NULL,
+            content TEXT NOT NULL,
+            diff_json TEXT NOT NULL,
+            created_at DATETIME NOT NULL,
+            FOREIGN KEY(approval_id) REFERENCES approvals(id),
+            UNIQUE (approval_id, revision)
+        );",
+    )
+}
+
+fn approval_policies(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE agents ADD COLUMN approval_policy TEXT;
+        ALTER TABLE approvals ADD COLUMN expires_at DATETIME;
+        ALTER TABLE approvals ADD COLUMN reminded_at DATETIME;
+        ALTER TABLE approvals ADD COLUMN superseded_by INTEGER;",
+    )
+}
+
+// Approvals decided before this are left as `Approved`; whether they were
+// actually posted was never recorded.
+fn approval_execution(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE approvals ADD COLUMN run_id INTEGER;
+        ALTER TABLE approvals ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE approvals ADD COLUMN error TEXT;",
+    )
+}
+
+// One row per idempotency key: the runner's record of what it has published.
+fn publications(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE publications (
+            idempotency_key TEXT PRIMARY KEY,
+            approval_id INTEGER NOT NULL,
+            run_id INTEGER,
+            status TEXT NOT NULL,
+            started_at DATETIME NOT NULL,
+            finished_at DATETIME,
+            FOREIGN KEY(approval_id) REFERENCES approvals(id)
+        );",
+    )
+}
+
+// `published_content` keeps the text of everything published, for the
+// content policy's duplicate check.
+fn content_policy(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE approvals ADD COLUMN policy_json TEXT;
+        CREATE TABLE published_content (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            agent_id INTEGER,
+            platform TEXT NOT NULL,
+            content TEXT NOT NULL,
+            published_at DATETIME NOT NULL
+        );
+        CREATE INDEX idx_published_content_platform ON published_content (platform, published_at);",
+    )
+}
+
+// The missed slot a catch-up goes on to once the current slot's retries settle.
+fn catch_up_retries(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE agents ADD COLUMN catch_up_at DATETIME;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated(legacy_db: Option<&Path>) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn, legacy_db).unwrap();
+        conn
+    }
+
+    fn applied(conn: &Connection) -> usize {
+        conn.query_row("SELECT COUNT(*) FROM logs WHERE message LIKE 'Applied database migration %'", [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn versions_are_contiguous_and_ascending() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i32 + 1, "migration '{}' is out of order", migration.name);
+        }
+    }
+
+    #[test]
+    fn fresh_database_gets_every_migration_once() {
+        let mut conn = migrated(None);
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i32);
+        assert_eq!(applied(&conn), MIGRATIONS.len());
+        for table in ["agents", "runs", "secrets", "approval_revisions", "publications", "published_content"] {
+            assert!(table_exists(&conn, "main", table).unwrap(), "missing table {}", table);
+        }
+
+        run(&mut conn, None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i32);
+        assert_eq!(applied(&conn), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn only_pending_migrations_run() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply(&mut conn, &MIGRATIONS.iter().take(5).collect::<Vec<_>>()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 5);
+
+        run(&mut conn, None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i32);
+        assert_eq!(applied(&conn), MIGRATIONS.len());
+    }
+
+    // Databases from before migrations existed: the original tables, no user_version.
+    #[test]
+    fn unversioned_database_is_upgraded_in_place() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE agents (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                description TEXT,
+                openclaw_task TEXT NOT NULL,
+                schedule TEXT,
+                status TEXT DEFAULT 'Active',
+                last_run_at DATETIME,
+                next_run_at DATETIME
+            );
+            CREATE TABLE logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_id INTEGER,
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+                message TEXT,
+                level TEXT,
+                FOREIGN KEY(agent_id) REFERENCES agents(id)
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+            CREATE TABLE approvals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_id INTEGER,
+                content TEXT,
+                status TEXT DEFAULT 'Pending'
+            );
+            INSERT INTO agents (name, openclaw_task, schedule) VALUES ('Trending LinkedIn Agent', 'Find trending topics and post', '0 9 * * *');
+            INSERT INTO agents (name, openclaw_task) VALUES ('Mystery', 'do something');
+            INSERT INTO approvals (agent_id, content) VALUES (1, 'A post');
+            INSERT INTO settings (key, value) VALUES ('llm_provider', 'ollama');",
+        )
+        .unwrap();
+
+        run(&mut conn, None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i32);
+
+        let tasks: Vec<String> = conn
+            .prepare("SELECT openclaw_task FROM agents ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(tasks, vec![r#"{"kind":"trending_post","params":{}}"#.to_string(), "do something".to_string()]);
+
+        let (draft, status): (String, String) = conn.query_row("SELECT draft, status FROM approvals", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!((draft.as_str(), status.as_str()), ("A post", "Pending"));
+        let created_at: Option<String> = conn.query_row("SELECT created_at FROM agents WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert!(created_at.is_some());
+    }
+
+    // A database as the old `commands.rs` stack left it, with one agent
+    fn legacy_db(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("personaliz-legacy-{}-{}.db", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        let legacy = Connection::open(&path).unwrap();
+        legacy
+            .execute_batch(
+                r#"CREATE TABLE agents (id TEXT PRIMARY KEY, name TEXT, description TEXT, config_json TEXT, created_at TEXT);
+                CREATE TABLE schedules (id TEXT PRIMARY KEY, agent_id TEXT, cron_expr TEXT, next_run TEXT);
+                CREATE TABLE logs (id TEXT PRIMARY KEY, agent_id TEXT, level TEXT, message TEXT, ts TEXT);
+                CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT);
+                INSERT INTO agents VALUES ('a-uuid', 'Poster', 'posts', '{"automation":{"script":"linkedin_post"}}', '2024-01-01');
+                INSERT INTO schedules VALUES ('s-uuid', 'a-uuid', '0 9 * * *', NULL);
+                INSERT INTO logs VALUES ('l-uuid', 'a-uuid', 'ERROR', 'it broke', '2024-01-02T03:04:05+02:00');
+                INSERT INTO settings VALUES ('theme', 'dark');"#,
+            )
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn legacy_command_database_is_folded_in() {
+        let path = legacy_db("fold");
+        let conn = migrated(Some(&path));
+        let _ = std::fs::remove_file(&path);
+
+        let (id, name, task, schedule, status): (i32, String, String, Option<String>, String) = conn
+            .query_row("SELECT id, name, openclaw_task, schedule, status FROM agents", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .unwrap();
+        assert_eq!(name, "Poster");
+        assert_eq!(status, "Paused");
+        assert_eq!(task, r#"{"kind":"trending_post","params":{}}"#);
+        assert_eq!(schedule.as_deref(), Some("0 9 * * *"));
+
+        let (agent_id, level, timestamp): (Option<i32>, String, String) = conn
+            .query_row("SELECT agent_id, level, timestamp FROM logs WHERE message = 'it broke'", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap();
+        assert_eq!((agent_id, level.as_str(), timestamp.as_str()), (Some(id), "Error", "2024-01-02 01:04:05"));
+
+        let theme: String = conn.query_row("SELECT value FROM settings WHERE key = 'theme'", [], |r| r.get(0)).unwrap();
+        assert_eq!(theme, "dark");
+        let attached: Option<String> = conn
+            .query_row("SELECT name FROM pragma_database_list WHERE name = ?", [LEGACY_SCHEMA], |r| r.get(0))
+            .optional()
+            .unwrap();
+        assert_eq!(attached, None);
+    }
+
+    #[test]
+    fn legacy_database_is_ignored_once_migrated() {
+        let path = legacy_db("once");
+        let mut conn = migrated(Some(&path));
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "INSERT INTO agents VALUES ('b-uuid', 'Commenter', NULL, '{}', '2024-02-01');
+                INSERT INTO logs VALUES ('m-uuid', 'b-uuid', 'INFO', 'later', '2024-02-02T00:00:00Z');",
+            )
+            .unwrap();
+
+        run(&mut conn, Some(&path)).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM agents"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM logs WHERE message IN ('it broke', 'later')"), 1);
+    }
+}
@@ -1,37 +1,619 @@
-use std::process::Command;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+
+pub const DEFAULT_SCRIPT_TIMEOUT: Duration = Duration::from_secs(300);
+// How long a script gets to close its browser after SIGTERM before it is killed
+const TERMINATE_GRACE: Duration = Duration::from_secs(3);
+// Upper bound on draining pipes once the process is gone
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub action: String, // e.g. post, comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub content: String,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OpenClawResult {
     pub success: bool,
+    pub exit_code: Option<i32>,
     pub output: String,
     pub error: Option<String>,
+    #[serde(default)]
+    pub timed_out: bool,
+    #[serde(default)]
+    pub cancelled: bool,
+    // The process was spawned; false for failures raised before it could start
+    #[serde(default)]
+    pub started: bool,
+    // Parsed protocol events, for scripts only
+    #[serde(default)]
+    pub events: Vec<ScriptEvent>,
 }
 
-pub fn search_trends() -> String {
-    // Mocking OpenClaw trend search for demo
-    "OpenClaw v2.0 is trending! New features include multi-modal agentic workflows and enhanced Tauri integration. #OpenClaw #AI"
-    .to_string()
-}
+// Version of the stdout protocol spoken by `scripts/protocol.cjs`
+pub const PROTOCOL_VERSION: u64 = 1;
 
-pub fn run_script(script_name: &str, arg: &str) -> OpenClawResult {
-    let output = Command::new("node")
-        .arg(format!("scripts/{}", script_name))
-        .arg(arg)
-        .output();
+// One line of script stdout: {"v":1,"type":"progress",...}.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptEvent {
+    Progress {
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        percent: Option<f64>,
+    },
+    Artifact {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    Result {
+        #[serde(default)]
+        summary: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<serde_json::Value>,
+    },
+    // What a dry run would have published, as seen by the mock target
+    Capture(Capture),
+    // Sent right before and right after the step that makes content public,
+    // so a failed post can be told apart from one that went out
+    Publishing,
+    Published {
+        // An earlier attempt with the same idempotency key had already posted it
+        #[serde(default)]
+        already: bool,
+    },
+    Error {
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<String>,
+        // The script's own verdict on whether trying again could help
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retryable: Option<bool>,
+    },
+}
 
-    match output {
-        Ok(out) => {
-            OpenClawResult {
-                success: out.status.success(),
-                output: String::from_utf8_lossy(&out.stdout).to_string(),
-                error: if out.stderr.is_empty() { None } else { Some(String::from_utf8_lossy(&out.stderr).to_string()) },
-            }
+impl ScriptEvent {
+    pub fn parse(line: &str) -> Result<ScriptEvent, String> {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|_| "not a JSON event".to_string())?;
+        match value.get("v").and_then(|v| v.as_u64()) {
+            Some(PROTOCOL_VERSION) => {}
+            Some(v) => return Err(format!("unsupported protocol version {}", v)),
+            None => return Err("missing protocol version".to_string()),
         }
-        Err(e) => OpenClawResult {
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(self, ScriptEvent::Result { .. } | ScriptEvent::Error { .. })
+    }
+}
+
+impl OpenClawResult {
+    pub fn failed(error: String) -> Self {
+        OpenClawResult {
             success: false,
+            exit_code: None,
             output: "".to_string(),
-            error: Some(e.to_string()),
+            error: Some(error),
+            timed_out: false,
+            cancelled: false,
+            started: false,
+            events: Vec::new(),
+        }
+    }
+
+    // Summary from the script's `result` event, if it sent one.
+    pub fn summary(&self) -> Option<&str> {
+        self.events.iter().rev().find_map(|e| match e {
+            ScriptEvent::Result { summary, .. } => Some(summary.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn captures(&self) -> impl Iterator<Item = &Capture> {
+        self.events.iter().filter_map(|e| match e {
+            ScriptEvent::Capture(capture) => Some(capture),
+            _ => None,
+        })
+    }
+
+    // Whether the script reached the step that publishes, and whether it got past it.
+    pub fn publishing(&self) -> bool {
+        self.events.iter().any(|e| matches!(e, ScriptEvent::Publishing))
+    }
+
+    pub fn published(&self) -> bool {
+        self.events.iter().any(|e| matches!(e, ScriptEvent::Published { .. }))
+    }
+
+    // The script's `error` event, if it sent one.
+    pub fn script_error(&self) -> Option<&ScriptEvent> {
+        self.events.iter().rev().find(|e| matches!(e, ScriptEvent::Error { .. }))
+    }
+
+    fn violation(mut self, problem: String) -> Self {
+        self.success = false;
+        let stderr = self.error.take().unwrap_or_default();
+        self.error = Some(with_stderr(&format!("Protocol violation: {}", problem), &stderr));
+        self
+    }
+
+    // Holds a finished script to the protocol: only events on stdout, ending
+    // in exactly one `result` or `error`. Anything else counts as a failure,
+    // even with exit code 0.
+    fn check_protocol(mut self) -> Self {
+        // A stopped script never got to finish, but its checkpoints still count
+        if self.timed_out || self.cancelled {
+            self.events = self.output.lines().filter_map(|l| ScriptEvent::parse(l.trim()).ok()).collect();
+            return self;
+        }
+        let mut events = Vec::new();
+        for (n, line) in self.output.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            match ScriptEvent::parse(line.trim()) {
+                Ok(event) => events.push(event),
+                Err(e) => {
+                    self.events = events;
+                    return self.violation(format!("stdout line {}: {}", n + 1, e));
+                }
+            }
+        }
+        let terminal: Vec<usize> = events.iter().enumerate().filter(|(_, e)| e.is_terminal()).map(|(i, _)| i).collect();
+        self.events = events;
+        match terminal.as_slice() {
+            [] => self.violation("script ended without a result or error event".into()),
+            [i] if *i + 1 != self.events.len() => self.violation("events after the final result".into()),
+            [_] => match self.events.last() {
+                Some(ScriptEvent::Error { message, code, .. }) => {
+                    self.success = false;
+                    let stderr = self.error.take().unwrap_or_default();
+                    let code = code.as_deref().map(|c| format!(" ({})", c)).unwrap_or_default();
+                    self.error = Some(with_stderr(&format!("{}{}", message, code), &stderr));
+                    self
+                }
+                // A result with a non-zero exit keeps its failure
+                _ => self,
+            },
+            _ => self.violation("more than one result or error event".into()),
+        }
+    }
+}
+
+// An error message leads with the reason; the script's stderr follows as detail.
+fn with_stderr(reason: &str, stderr: &str) -> String {
+    match stderr.trim_end() {
+        "" => reason.to_string(),
+        stderr => format!("{}\n{}", reason, stderr),
+    }
+}
+
+enum Stop {
+    TimedOut,
+    Cancelled,
+}
+
+// Resolves once the run is cancelled, or never if nobody can cancel it.
+pub async fn cancelled(cancel: &mut watch::Receiver<bool>) {
+    while !*cancel.borrow_and_update() {
+        if cancel.changed().await.is_err() {
+            // Nobody can cancel any more
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pipe {
+    Stdout,
+    Stderr,
+}
+
+// Receives each output line as it is written. Must not block: it runs on the
+// task that reads the pipe.
+pub type LineSink = Arc<dyn Fn(Pipe, &str) + Send + Sync>;
+
+// A pipe being read in the background. Lines land in `collected` as they
+// arrive, so they survive even when the pipe never closes, e.g. because a
+// grandchild the kill missed still holds it open.
+struct Drain {
+    task: tokio::task::JoinHandle<()>,
+    collected: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Drain {
+    // Waits up to `limit` for the pipe to close, then returns what was read.
+    async fn finish(mut self, limit: Duration) -> String {
+        if tokio::time::timeout(limit, &mut self.task).await.is_err() {
+            self.task.abort();
+        }
+        let collected = std::mem::take(&mut *self.collected.lock().unwrap());
+        String::from_utf8_lossy(&collected).to_string()
+    }
+}
+
+fn drain<R: tokio::io::AsyncRead + Unpin + Send + 'static>(pipe: Option<R>, which: Pipe, sink: Option<LineSink>) -> Drain {
+    let collected = Arc::new(Mutex::new(Vec::new()));
+    let buf = collected.clone();
+    let task = tokio::spawn(async move {
+        let Some(pipe) = pipe else { return };
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if let Some(sink) = &sink {
+                        sink(which, String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']));
+                    }
+                    buf.lock().unwrap().extend_from_slice(&line);
+                }
+            }
+        }
+    });
+    Drain { task, collected }
+}
+
+// Stops the script and everything it spawned (Playwright's browser processes
+// included): the script runs in its own process group on unix, and `taskkill /T`
+// walks the tree on Windows.
+async fn terminate_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(unix)]
+        {
+            let group = format!("-{}", pid);
+            let _ = std::process::Command::new("kill").args(["-TERM", "--", &group]).status();
+            if tokio::time::timeout(TERMINATE_GRACE, child.wait()).await.is_err() {
+                let _ = std::process::Command::new("kill").args(["-KILL", "--", &group]).status();
+            }
+        }
+        #[cfg(windows)]
+        {
+            let _ = std::process::Command::new("taskkill")
+                .args(["/T", "/F", "/PID", &pid.to_string()])
+                .status();
+        }
+    }
+    let _ = child.kill().await;
+}
+
+// Runs `program` to completion, or until `timeout` or `cancel` stops it along
+// with everything it spawned. Output still held open by a stray grandchild is
+// given up on after `drain_timeout`.
+pub async fn run_command_async(
+    program: &OsStr,
+    args: &[String],
+    timeout: Duration,
+    drain_timeout: Duration,
+    cancel: watch::Receiver<bool>,
+    sink: Option<LineSink>,
+) -> OpenClawResult {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    run_async(cmd, timeout, drain_timeout, cancel, sink).await
+}
+
+async fn run_async(mut cmd: Command, timeout: Duration, drain_timeout: Duration, mut cancel: watch::Receiver<bool>, sink: Option<LineSink>) -> OpenClawResult {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return OpenClawResult::failed(e.to_string()),
+    };
+    let stdout = drain(child.stdout.take(), Pipe::Stdout, sink.clone());
+    let stderr = drain(child.stderr.take(), Pipe::Stderr, sink);
+
+    let finished = tokio::select! {
+        status = child.wait() => Ok(status),
+        _ = tokio::time::sleep(timeout) => Err(Stop::TimedOut),
+        _ = cancelled(&mut cancel) => Err(Stop::Cancelled),
+    };
+    if finished.is_err() {
+        terminate_tree(&mut child).await;
+    }
+
+    let (output, stderr) = tokio::join!(stdout.finish(drain_timeout), stderr.finish(drain_timeout));
+
+    match finished {
+        Ok(Ok(status)) => OpenClawResult {
+            success: status.success(),
+            exit_code: status.code(),
+            output,
+            error: if stderr.is_empty() { None } else { Some(stderr) },
+            timed_out: false,
+            cancelled: false,
+            started: true,
+            events: Vec::new(),
         },
+        Ok(Err(e)) => OpenClawResult { output, started: true, ..OpenClawResult::failed(e.to_string()) },
+        Err(Stop::TimedOut) => OpenClawResult {
+            output,
+            timed_out: true,
+            started: true,
+            ..OpenClawResult::failed(with_stderr(&format!("Timed out after {}s", timeout.as_secs_f64()), &stderr))
+        },
+        Err(Stop::Cancelled) => OpenClawResult {
+            output,
+            cancelled: true,
+            started: true,
+            ..OpenClawResult::failed(with_stderr("Cancelled", &stderr))
+        },
+    }
+}
+
+// `script` is an absolute path from `Scripts::resolve`; `node_path` lets it
+// find packages outside its own directory. `env` is added to the script's
+// environment.
+pub async fn run_script_async(
+    script: &Path,
+    node_path: &OsStr,
+    env: &[(String, String)],
+    arg: &str,
+    timeout: Duration,
+    cancel: watch::Receiver<bool>,
+    sink: Option<LineSink>,
+) -> OpenClawResult {
+    let mut cmd = Command::new("node");
+    cmd.arg(script).arg(arg).env("NODE_PATH", node_path).envs(env.iter().map(|(k, v)| (k, v)));
+    if let Some(dir) = script.parent() {
+        cmd.current_dir(dir);
+    }
+    run_async(cmd, timeout, DRAIN_TIMEOUT, cancel, sink).await.check_protocol()
+}
+
+// `run_script_async` for task code, which runs on the blocking pool. Called from
+// an async worker it would stall that worker until the script exits.
+pub fn run_script(
+    script: &Path,
+    node_path: &OsStr,
+    env: &[(String, String)],
+    arg: &str,
+    timeout: Duration,
+    cancel: watch::Receiver<bool>,
+    sink: Option<LineSink>,
+) -> OpenClawResult {
+    tauri::async_runtime::block_on(run_script_async(script, node_path, env, arg, timeout, cancel, sink))
+}
+
+// Why an OpenClaw CLI call failed. The CLI reports usage errors with exit code
+// 2, missing or expired logins with 3 and rate limiting with 4; anything else
+// is classified from stderr where possible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpenClawError {
+    NotInstalled,
+    Usage(String),
+    Auth(String),
+    RateLimited(String),
+    Network(String),
+    TimedOut,
+    Cancelled,
+    Failed { exit_code: Option<i32>, stderr: String },
+    // The CLI succeeded but its stdout was not the JSON we asked for
+    InvalidOutput(String),
+}
+
+impl std::fmt::Display for OpenClawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenClawError::NotInstalled => write!(f, "The OpenClaw CLI is not installed or not on PATH"),
+            OpenClawError::Usage(e) => write!(f, "OpenClaw rejected the command: {}", e),
+            OpenClawError::Auth(e) => write!(f, "OpenClaw is not logged in: {}", e),
+            OpenClawError::RateLimited(e) => write!(f, "OpenClaw was rate limited: {}", e),
+            OpenClawError::Network(e) => write!(f, "OpenClaw could not reach the network: {}", e),
+            OpenClawError::TimedOut => write!(f, "OpenClaw timed out"),
+            OpenClawError::Cancelled => write!(f, "OpenClaw call was cancelled"),
+            OpenClawError::Failed { exit_code: Some(code), stderr } => write!(f, "OpenClaw exited with code {}: {}", code, stderr),
+            OpenClawError::Failed { exit_code: None, stderr } => write!(f, "OpenClaw failed: {}", stderr),
+            OpenClawError::InvalidOutput(e) => write!(f, "OpenClaw returned unexpected output: {}", e),
+        }
+    }
+}
+
+impl OpenClawError {
+    pub fn classify(result: &OpenClawResult) -> OpenClawError {
+        let stderr = result.error.clone().unwrap_or_default().trim().to_string();
+        let lower = stderr.to_lowercase();
+        if result.cancelled {
+            return OpenClawError::Cancelled;
+        }
+        if result.timed_out {
+            return OpenClawError::TimedOut;
+        }
+        match result.exit_code {
+            Some(2) => OpenClawError::Usage(stderr),
+            Some(3) => OpenClawError::Auth(stderr),
+            Some(4) => OpenClawError::RateLimited(stderr),
+            _ if lower.contains("not logged in") || lower.contains("unauthorized") => OpenClawError::Auth(stderr),
+            _ if lower.contains("rate limit") || lower.contains("too many requests") => OpenClawError::RateLimited(stderr),
+            _ if ["econnreset", "econnrefused", "etimedout", "enotfound", "network"].iter().any(|p| lower.contains(p)) => {
+                OpenClawError::Network(stderr)
+            }
+            exit_code => OpenClawError::Failed { exit_code, stderr },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trend {
+    pub title: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Talks to the `openclaw` CLI installed by `install_openclaw`. Every call asks
+// for JSON output; the raw result is handed back too so callers can keep it in
+// the run record.
+pub struct OpenClawClient {
+    pub binary: PathBuf,
+    // Where live output goes, if anyone is watching
+    pub output: Option<LineSink>,
+    pub drain_timeout: Duration,
+}
+
+pub type Call<T> = (OpenClawResult, Result<T, OpenClawError>);
+
+impl OpenClawClient {
+    pub fn locate() -> Result<OpenClawClient, OpenClawError> {
+        which::which("openclaw")
+            .map(|binary| OpenClawClient { binary, output: None, drain_timeout: DRAIN_TIMEOUT })
+            .map_err(|_| OpenClawError::NotInstalled)
+    }
+
+    pub async fn call<T: DeserializeOwned>(&self, args: &[String], timeout: Duration, cancel: watch::Receiver<bool>) -> Call<T> {
+        let mut args = args.to_vec();
+        args.push("--json".to_string());
+        let result = run_command_async(self.binary.as_os_str(), &args, timeout, self.drain_timeout, cancel, self.output.clone()).await;
+        let value = if result.success {
+            serde_json::from_str(result.output.trim()).map_err(|e| OpenClawError::InvalidOutput(e.to_string()))
+        } else {
+            Err(OpenClawError::classify(&result))
+        };
+        (result, value)
+    }
+
+    pub async fn search_trends(&self, query: &str, limit: u32, timeout: Duration, cancel: watch::Receiver<bool>) -> Call<Vec<Trend>> {
+        let args = ["trends".into(), "search".into(), query.into(), "--limit".into(), limit.to_string()];
+        self.call(&args, timeout, cancel).await
+    }
+
+    // Runs one of the CLI's own automation tasks with a JSON input.
+    pub async fn run_task(&self, task: &str, input: &serde_json::Value, timeout: Duration, cancel: watch::Receiver<bool>) -> Call<serde_json::Value> {
+        let args = ["run".into(), task.into(), "--input".into(), input.to_string()];
+        self.call(&args, timeout, cancel).await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    // An `openclaw` stand-in that runs `body` as a shell script.
+    fn stub(name: &str, body: &str) -> OpenClawClient {
+        let dir = std::env::temp_dir().join(format!("personaliz-openclaw-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let binary = dir.join("openclaw");
+        std::fs::write(&binary, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        OpenClawClient { binary, output: None, drain_timeout: DRAIN_TIMEOUT }
+    }
+
+    async fn search(client: &OpenClawClient, timeout: Duration) -> Result<Vec<Trend>, OpenClawError> {
+        let (_cancel, rx) = watch::channel(false);
+        client.search_trends("openclaw", 3, timeout, rx).await.1
+    }
+
+    #[tokio::test]
+    async fn locates_the_cli_on_path() {
+        let stubbed = stub("path", r#"echo '[{"title":"Found on PATH"}]'"#);
+        let dir = stubbed.binary.parent().unwrap().to_path_buf();
+        // Only prepended, so other tests still find their tools
+        let original = std::env::var_os("PATH").unwrap_or_default();
+        let path = std::env::join_paths(std::iter::once(dir).chain(std::env::split_paths(&original))).unwrap();
+        std::env::set_var("PATH", &path);
+        let located = OpenClawClient::locate();
+        std::env::set_var("PATH", original);
+
+        let client = located.unwrap();
+        assert_eq!(client.binary, stubbed.binary);
+        assert_eq!(search(&client, Duration::from_secs(10)).await.unwrap()[0].title, "Found on PATH");
+    }
+
+    #[tokio::test]
+    async fn parses_json_output() {
+        let client = stub("ok", r#"echo '[{"title":"OpenClaw 2.0","tags":["ai"]}]'"#);
+        let trends = search(&client, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(trends.len(), 1);
+        assert_eq!(trends[0].title, "OpenClaw 2.0");
+        assert_eq!(trends[0].tags, vec!["ai".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn maps_exit_codes_to_errors() {
+        let stderr = || "went wrong".to_string();
+        let cases = [(2, OpenClawError::Usage(stderr())), (3, OpenClawError::Auth(stderr())), (4, OpenClawError::RateLimited(stderr()))];
+        for (code, expected) in cases {
+            let client = stub(&format!("exit{}", code), &format!("echo 'went wrong' >&2; exit {}", code));
+            assert_eq!(search(&client, Duration::from_secs(10)).await.unwrap_err(), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn classifies_other_failures_from_stderr() {
+        let client = stub("network", "echo 'connect ECONNREFUSED 127.0.0.1:443' >&2; exit 1");
+        assert!(matches!(search(&client, Duration::from_secs(10)).await, Err(OpenClawError::Network(_))));
+        let client = stub("failed", "echo 'boom' >&2; exit 9");
+        assert_eq!(
+            search(&client, Duration::from_secs(10)).await.unwrap_err(),
+            OpenClawError::Failed { exit_code: Some(9), stderr: "boom".into() }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_output_that_is_not_json() {
+        let client = stub("garbage", "echo 'Found 3 trends'");
+        assert!(matches!(search(&client, Duration::from_secs(10)).await, Err(OpenClawError::InvalidOutput(_))));
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let client = stub("slow", "sleep 30");
+        let started = std::time::Instant::now();
+        assert_eq!(search(&client, Duration::from_millis(300)).await.unwrap_err(), OpenClawError::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn keeps_output_when_a_grandchild_holds_the_pipe() {
+        let client = OpenClawClient {
+            drain_timeout: Duration::from_millis(300),
+            ..stub("grandchild", r#"echo '[{"title":"Kept"}]'; sleep 8 &"#)
+        };
+        let started = std::time::Instant::now();
+        let trends = search(&client, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(trends[0].title, "Kept");
+        assert!(started.elapsed() < DRAIN_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn stop_reason_comes_before_stderr() {
+        let client = stub("reason", "echo 'still loading' >&2; sleep 30");
+        let (_cancel, rx) = watch::channel(false);
+        let result = run_command_async(client.binary.as_os_str(), &[], Duration::from_millis(500), DRAIN_TIMEOUT, rx, None).await;
+        assert_eq!(result.error.as_deref(), Some("Timed out after 0.5s\nstill loading"));
+    }
+
+    #[tokio::test]
+    async fn can_be_cancelled() {
+        let client = stub("cancelled", "sleep 30");
+        let (cancel, rx) = watch::channel(false);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let _ = cancel.send(true);
+        });
+        let (_, trends) = client.search_trends("openclaw", 3, Duration::from_secs(30), rx).await;
+        assert_eq!(trends.unwrap_err(), OpenClawError::Cancelled);
     }
 }
@@ -0,0 +1,286 @@
+use crate::scripts::Scripts;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Component, Path, PathBuf};
+use tauri::State;
+
+pub const MANIFEST: &str = "plugin.json";
+const ENTRYPOINT_EXTENSIONS: &[&str] = &["cjs", "js", "mjs"];
+
+// An automation plugin is a directory under `plugins/` in either script
+// directory (see `Scripts`) holding a `plugin.json` manifest and the script it
+// names as its entrypoint. User plugins shadow bundled ones with the same name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    // Path of the script, relative to the plugin directory
+    pub entrypoint: String,
+    #[serde(default)]
+    pub params: Vec<PluginParam>,
+    // Vault secrets handed to the script as environment variables
+    #[serde(default)]
+    pub secrets: Vec<RequiredSecret>,
+    // Site whose saved login (see `sessions`) the script runs with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    // Posts or comments publicly; runs are held for approval first
+    #[serde(default)]
+    pub publishes: bool,
+    // Honours PERSONALIZ_DRY_RUN by only touching the mock targets; plugins
+    // without it are not started in dry runs
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PluginParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: ParamType,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamType {
+    String,
+    Number,
+    Boolean,
+    Object,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RequiredSecret {
+    pub provider: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Browser,
+    Network,
+    Filesystem,
+    Clipboard,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub manifest: PluginManifest,
+    pub dir: PathBuf,
+}
+
+// One discovered plugin directory, valid or not, as shown to the user.
+#[derive(Serialize, Debug, Clone)]
+pub struct Automation {
+    pub id: String,
+    pub source: String, // bundled, user
+    pub dir: String,
+    pub manifest: Option<PluginManifest>,
+    pub error: Option<String>,
+}
+
+impl ParamType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ParamType::String => "string",
+            ParamType::Number => "number",
+            ParamType::Boolean => "boolean",
+            ParamType::Object => "object",
+        }
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            ParamType::String => value.is_string(),
+            ParamType::Number => value.is_number(),
+            ParamType::Boolean => value.is_boolean(),
+            ParamType::Object => value.is_object(),
+        }
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+impl PluginManifest {
+    pub fn validate(&self, dir: &Path) -> Result<(), String> {
+        if !valid_name(&self.name) {
+            return Err(format!("Plugin name '{}' may only use lowercase letters, digits, '-' and '_'", self.name));
+        }
+        let version: Vec<&str> = self.version.split('.').collect();
+        if version.len() != 3 || version.iter().any(|p| p.parse::<u32>().is_err()) {
+            return Err(format!("Version '{}' is not of the form MAJOR.MINOR.PATCH", self.version));
+        }
+
+        let entrypoint = Path::new(&self.entrypoint);
+        if !entrypoint.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(format!("Entrypoint '{}' must be a relative path inside the plugin directory", self.entrypoint));
+        }
+        if !entrypoint.extension().and_then(|e| e.to_str()).is_some_and(|e| ENTRYPOINT_EXTENSIONS.contains(&e)) {
+            return Err(format!("Entrypoint '{}' must be a .cjs, .js or .mjs script", self.entrypoint));
+        }
+        if !dir.join(entrypoint).is_file() {
+            return Err(format!("Entrypoint '{}' does not exist", self.entrypoint));
+        }
+
+        for (i, param) in self.params.iter().enumerate() {
+            if param.name.trim().is_empty() {
+                return Err("Parameter names cannot be empty".into());
+            }
+            if self.params[..i].iter().any(|p| p.name == param.name) {
+                return Err(format!("Parameter '{}' is declared twice", param.name));
+            }
+            if param.default.as_ref().is_some_and(|d| !param.kind.accepts(d)) {
+                return Err(format!("Default for parameter '{}' is not a {}", param.name, param.kind.as_str()));
+            }
+        }
+        if let Some(secret) = self.secrets.iter().find(|s| s.provider.trim().is_empty() || s.name.trim().is_empty()) {
+            return Err(format!("Secret '{}/{}' needs both a provider and a name", secret.provider, secret.name));
+        }
+        if let Some(site) = self.session.as_deref().filter(|s| crate::sessions::key(s, "default").is_err()) {
+            return Err(format!("Session site '{}' is not a valid site name", site));
+        }
+        Ok(())
+    }
+}
+
+impl Plugin {
+    pub fn entrypoint(&self) -> PathBuf {
+        self.dir.join(&self.manifest.entrypoint)
+    }
+
+    // Checks `input` against the declared parameters and fills in defaults.
+    pub fn bind(&self, input: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+        let name = &self.manifest.name;
+        if let Some(unknown) = input.keys().find(|k| !self.manifest.params.iter().any(|p| &p.name == *k)) {
+            return Err(format!("Plugin '{}' has no parameter '{}'", name, unknown));
+        }
+        let mut bound = Map::new();
+        for param in &self.manifest.params {
+            match input.get(&param.name).filter(|v| !v.is_null()).or(param.default.as_ref()) {
+                Some(value) if param.kind.accepts(value) => {
+                    bound.insert(param.name.clone(), value.clone());
+                }
+                Some(_) => return Err(format!("Parameter '{}' of plugin '{}' must be a {}", param.name, name, param.kind.as_str())),
+                None if param.required => return Err(format!("Plugin '{}' requires parameter '{}'", name, param.name)),
+                None => {}
+            }
+        }
+        Ok(bound)
+    }
+}
+
+// Environment variable a required secret is passed in, e.g. PERSONALIZ_SECRET_LINKEDIN_SESSION.
+pub fn secret_env(secret: &RequiredSecret) -> String {
+    let raw = format!("PERSONALIZ_SECRET_{}_{}", secret.provider, secret.name);
+    raw.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect()
+}
+
+fn load(dir: &Path) -> Result<PluginManifest, String> {
+    let raw = std::fs::read_to_string(dir.join(MANIFEST)).map_err(|e| format!("Cannot read {}: {}", MANIFEST, e))?;
+    let manifest: PluginManifest = serde_json::from_str(&raw).map_err(|e| format!("Invalid {}: {}", MANIFEST, e))?;
+    manifest.validate(dir)?;
+    Ok(manifest)
+}
+
+// User plugins first, so they win over bundled ones of the same name.
+fn roots(scripts: &Scripts) -> [(&'static str, PathBuf); 2] {
+    [("user", scripts.overrides.join("plugins")), ("bundled", scripts.bundled.join("plugins"))]
+}
+
+pub fn discover(scripts: &Scripts) -> Vec<Automation> {
+    let mut found: Vec<Automation> = Vec::new();
+    for (source, root) in roots(scripts) {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        let mut dirs: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.join(MANIFEST).is_file()).collect();
+        dirs.sort();
+        for dir in dirs {
+            let folder = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let (id, mut manifest, mut error) = match load(&dir) {
+                Ok(m) => (m.name.clone(), Some(m), None),
+                Err(e) => (folder, None, Some(e)),
+            };
+            if manifest.is_some() {
+                if let Some(winner) = found.iter().find(|a| a.id == id && a.manifest.is_some()) {
+                    error = Some(format!("Shadowed by the {} plugin in {}", winner.source, winner.dir));
+                    manifest = None;
+                }
+            }
+            found.push(Automation { id, source: source.into(), dir: dir.display().to_string(), manifest, error });
+        }
+    }
+    found.sort_by(|a, b| a.id.cmp(&b.id));
+    found
+}
+
+pub fn find(scripts: &Scripts, name: &str) -> Result<Plugin, String> {
+    discover(scripts)
+        .into_iter()
+        .find_map(|a| match a.manifest {
+            Some(manifest) if a.id == name => Some(Plugin { manifest, dir: PathBuf::from(a.dir) }),
+            _ => None,
+        })
+        .ok_or_else(|| format!("Automation plugin '{}' is not installed", name))
+}
+
+#[tauri::command]
+pub fn list_automations(scripts: State<Scripts>) -> Vec<Automation> {
+    discover(&scripts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The repo's own `scripts/`, next to the crate's manifest directory
+    fn bundled() -> Scripts {
+        let scripts = Path::new(env!("CARGO_MANIFEST_DIR")).join("../scripts");
+        Scripts::new(scripts, std::env::temp_dir().join("personaliz-no-overrides"))
+    }
+
+    #[test]
+    fn built_in_automations_are_plugins() {
+        let scripts = bundled();
+        for name in crate::tasks::kinds().iter().flat_map(|k| k.plugins()) {
+            let plugin = find(&scripts, name).unwrap();
+            assert!(plugin.manifest.publishes, "{}", name);
+            assert_eq!(plugin.manifest.session.as_deref(), Some("linkedin"));
+        }
+        let listed: Vec<String> = discover(&scripts).into_iter().filter(|a| a.error.is_none()).map(|a| a.id).collect();
+        assert_eq!(listed, ["linkedin_comment", "linkedin_post"]);
+    }
+
+    #[test]
+    fn plugin_entries_take_approval_from_the_manifest() {
+        let dir = std::env::temp_dir().join(format!("personaliz-plugins-{}", std::process::id()));
+        let plugin = dir.join("plugins").join("notify");
+        std::fs::create_dir_all(&plugin).unwrap();
+        std::fs::write(plugin.join("index.cjs"), "").unwrap();
+        std::fs::write(plugin.join(MANIFEST), r#"{"name":"notify","version":"1.0.0","entrypoint":"index.cjs"}"#).unwrap();
+        let scripts = Scripts::new(bundled().bundled, dir.clone());
+
+        let approval = |name: &str| {
+            crate::tasks::catalog(&scripts).into_iter().find(|k| k.plugin.as_deref() == Some(name)).map(|k| k.requires_approval)
+        };
+        assert_eq!(approval("notify"), Some(false));
+        assert_eq!(approval("linkedin_post"), Some(true));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
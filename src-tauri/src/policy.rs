@@ -0,0 +1,299 @@
+use crate::db::{self, DbState};
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tauri::State;
+
+const SETTING: &str = "content_policy";
+
+// Rules every draft is checked against before it is published, stored as JSON
+// in the `content_policy` setting. Lists are matched case-insensitively and
+// hashtags are written with or without the '#'.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContentPolicy {
+    pub blocked_words: Vec<String>,
+    // Regular expressions, e.g. "(?i)guaranteed returns"
+    pub blocked_patterns: Vec<String>,
+    // Characters allowed per platform, e.g. {"linkedin": 3000}
+    pub max_length: HashMap<String, usize>,
+    pub required_hashtags: Vec<String>,
+    pub forbidden_hashtags: Vec<String>,
+    // Domains links may point to (subdomains included); None allows any link
+    pub allowed_link_domains: Option<Vec<String>>,
+    // Email addresses and phone numbers
+    pub detect_pii: bool,
+    // How far back to look for a near-identical post on the same platform; None disables the check
+    pub duplicate_window_days: Option<u32>,
+    // Share of words two texts must have in common to count as duplicates, 0.0..=1.0
+    pub duplicate_similarity: f64,
+    pub on_violation: PolicyAction,
+    // Rules whose violations block even when `on_violation` holds for approval
+    pub block_rules: Vec<Rule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    // Fail the run; nothing is published
+    Block,
+    // Hold the draft for approval, even for kinds that normally publish directly
+    #[default]
+    RequireApproval,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    BlockedWord,
+    BlockedPattern,
+    MaxLength,
+    RequiredHashtag,
+    ForbiddenHashtag,
+    Link,
+    Pii,
+    Duplicate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub message: String,
+}
+
+pub enum Verdict {
+    Allow,
+    Hold(Vec<Violation>),
+    Block(Vec<Violation>),
+}
+
+impl Default for ContentPolicy {
+    fn default() -> Self {
+        ContentPolicy {
+            blocked_words: Vec::new(),
+            blocked_patterns: Vec::new(),
+            max_length: HashMap::from([("linkedin".to_string(), 3000)]),
+            required_hashtags: Vec::new(),
+            forbidden_hashtags: Vec::new(),
+            allowed_link_domains: None,
+            detect_pii: true,
+            duplicate_window_days: None,
+            duplicate_similarity: 0.9,
+            on_violation: PolicyAction::RequireApproval,
+            block_rules: Vec::new(),
+        }
+    }
+}
+
+fn hashtag_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"#([\p{L}\p{N}_]+)").unwrap())
+}
+
+fn link_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\b(?:https?://|www\.)[^\s<>()]+").unwrap())
+}
+
+fn email_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap())
+}
+
+fn phone_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\+?\(?\d[\d\s().-]{6,}\d").unwrap())
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// Whole-word match; word boundaries only apply where the word starts or ends
+// with a word character, so entries like "c++" still match.
+fn word_re(word: &str) -> Result<Regex, regex::Error> {
+    let word = word.trim();
+    let edge = |c: Option<char>| if c.is_some_and(|c| c.is_alphanumeric() || c == '_') { r"\b" } else { "" };
+    let pattern = format!("{}{}{}", edge(word.chars().next()), regex::escape(word), edge(word.chars().last()));
+    RegexBuilder::new(&pattern).case_insensitive(true).build()
+}
+
+// Host of a link found in the text, without a leading "www.".
+fn link_host(link: &str) -> Option<String> {
+    let link = link.trim_end_matches(['.', ',', '!', '?', ';', ':']);
+    let absolute = if link.to_lowercase().starts_with("www.") { format!("https://{}", link) } else { link.to_string() };
+    let host = url::Url::parse(&absolute).ok()?.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+// Jaccard similarity of the two texts' word sets.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+impl ContentPolicy {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let raw = conn
+            .query_row("SELECT value FROM settings WHERE key = ?", [SETTING], |row| row.get::<_, String>(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match raw {
+            Some(raw) => serde_json::from_str(&raw).map_err(|e| format!("The stored content policy is invalid: {}", e)),
+            None => Ok(ContentPolicy::default()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for pattern in &self.blocked_patterns {
+            Regex::new(pattern).map_err(|e| format!("Blocked pattern '{}' is not a valid regular expression: {}", pattern, e))?;
+        }
+        if let Some(word) = self.blocked_words.iter().find(|w| w.trim().is_empty()) {
+            return Err(format!("Blocked word '{}' is empty", word));
+        }
+        if let Some((platform, _)) = self.max_length.iter().find(|(_, max)| **max == 0) {
+            return Err(format!("Max length for {} must be positive", platform));
+        }
+        if self.duplicate_window_days == Some(0) {
+            return Err("duplicate_window_days must be at least 1".into());
+        }
+        if !(0.0..=1.0).contains(&self.duplicate_similarity) {
+            return Err("duplicate_similarity must be between 0.0 and 1.0".into());
+        }
+        Ok(())
+    }
+
+    // Every rule `text` breaks when published on `platform`.
+    pub fn evaluate(&self, conn: &Connection, platform: &str, text: &str) -> Result<Vec<Violation>, String> {
+        let mut found = Vec::new();
+        let mut violation = |rule, message: String| found.push(Violation { rule, message });
+
+        for word in &self.blocked_words {
+            if word_re(word).map_err(|e| e.to_string())?.is_match(text) {
+                violation(Rule::BlockedWord, format!("Contains the blocked word '{}'", word.trim()));
+            }
+        }
+        for pattern in &self.blocked_patterns {
+            if Regex::new(pattern).map_err(|e| e.to_string())?.is_match(text) {
+                violation(Rule::BlockedPattern, format!("Matches the blocked pattern '{}'", pattern));
+            }
+        }
+
+        if let Some(max) = self.max_length.get(platform) {
+            let length = text.chars().count();
+            if length > *max {
+                violation(Rule::MaxLength, format!("{} characters is over the {} limit of {}", length, platform, max));
+            }
+        }
+
+        let tags: HashSet<String> = hashtag_re().captures_iter(text).map(|c| c[1].to_lowercase()).collect();
+        for tag in &self.required_hashtags {
+            if !tags.contains(&normalize_tag(tag)) {
+                violation(Rule::RequiredHashtag, format!("Missing the required hashtag #{}", normalize_tag(tag)));
+            }
+        }
+        for tag in &self.forbidden_hashtags {
+            if tags.contains(&normalize_tag(tag)) {
+                violation(Rule::ForbiddenHashtag, format!("Uses the forbidden hashtag #{}", normalize_tag(tag)));
+            }
+        }
+
+        if let Some(allowed) = &self.allowed_link_domains {
+            for link in link_re().find_iter(text) {
+                let allowed = link_host(link.as_str()).is_some_and(|host| {
+                    allowed.iter().map(|d| d.trim().trim_start_matches("www.").to_lowercase()).any(|d| host == d || host.ends_with(&format!(".{}", d)))
+                });
+                if !allowed {
+                    violation(Rule::Link, format!("Links to {}, which is not an allowed domain", link.as_str()));
+                }
+            }
+        }
+
+        if self.detect_pii {
+            if let Some(email) = email_re().find(text) {
+                violation(Rule::Pii, format!("Contains an email address ({})", email.as_str()));
+            }
+            let without_links = link_re().replace_all(text, " ");
+            let phone = phone_re().find_iter(&without_links).find(|m| {
+                let digits = m.as_str().chars().filter(char::is_ascii_digit).count();
+                (9..=15).contains(&digits)
+            });
+            if let Some(phone) = phone {
+                violation(Rule::Pii, format!("Contains what looks like a phone number ({})", phone.as_str().trim()));
+            }
+        }
+
+        if let Some(days) = self.duplicate_window_days {
+            let since = db::format_timestamp(Utc::now() - chrono::Duration::days(days as i64));
+            let mut stmt = conn
+                .prepare("SELECT content, published_at FROM published_content WHERE platform = ? AND published_at >= ? ORDER BY id DESC")
+                .map_err(|e| e.to_string())?;
+            let recent = stmt
+                .query_map(params![platform, since], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(|e| e.to_string())?;
+            let mine = words(text);
+            if let Some((_, at)) = recent.iter().find(|(content, _)| similarity(&mine, &words(content)) >= self.duplicate_similarity) {
+                violation(Rule::Duplicate, format!("Nearly the same as what was published on {} at {}", platform, at));
+            }
+        }
+
+        Ok(found)
+    }
+
+    pub fn verdict(&self, violations: Vec<Violation>) -> Verdict {
+        if violations.is_empty() {
+            Verdict::Allow
+        } else if self.on_violation == PolicyAction::Block || violations.iter().any(|v| self.block_rules.contains(&v.rule)) {
+            Verdict::Block(violations)
+        } else {
+            Verdict::Hold(violations)
+        }
+    }
+}
+
+pub fn describe(violations: &[Violation]) -> String {
+    violations.iter().map(|v| v.message.as_str()).collect::<Vec<_>>().join("; ")
+}
+
+// Kept for the duplicate check.
+pub fn record_published(conn: &Connection, agent_id: i32, platform: &str, text: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO published_content (agent_id, platform, content, published_at) VALUES (?, ?, ?, CURRENT_TIMESTAMP)",
+        params![agent_id, platform, text],
+    )?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_content_policy(state: State<DbState>) -> Result<ContentPolicy, String> {
+    let conn = state.0.lock().unwrap();
+    ContentPolicy::load(&conn)
+}
+
+#[tauri::command]
+pub fn update_content_policy(state: State<DbState>, policy: ContentPolicy) -> Result<(), String> {
+    policy.validate()?;
+    let raw = serde_json::to_string(&policy).map_err(|e| e.to_string())?;
+    let conn = state.0.lock().unwrap();
+    conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)", [SETTING, raw.as_str()])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Checks a draft against the current policy without publishing anything.
+#[tauri::command]
+pub fn check_content(state: State<DbState>, platform: String, text: String) -> Result<Vec<Violation>, String> {
+    let conn = state.0.lock().unwrap();
+    ContentPolicy::load(&conn)?.evaluate(&conn, &platform, &text)
+}
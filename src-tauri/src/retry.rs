@@ -0,0 +1,125 @@
+use crate::openclaw::{OpenClawResult, ScriptEvent};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Per-agent retry configuration, stored as JSON in `agents.retry_policy`.
+// Only scheduled runs are retried; a manual run reports its failure directly.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryPolicy {
+    // Total attempts per scheduled occurrence, including the first one
+    pub max_attempts: u32,
+    pub backoff_base_secs: u64,
+    pub backoff_max_secs: u64,
+    // Fraction of the delay randomly added or removed, 0.0..=1.0
+    pub jitter: f64,
+    // Exit codes worth retrying; empty means any non-zero exit
+    pub retry_exit_codes: Vec<i32>,
+    pub retry_on_timeout: bool,
+    // stderr substrings marking a failure as permanent, checked before anything else
+    pub fatal_patterns: Vec<String>,
+    // stderr substrings marking a failure as transient even for other exit codes
+    pub transient_patterns: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff_base_secs: 60,
+            backoff_max_secs: 3600,
+            jitter: 0.2,
+            retry_exit_codes: Vec::new(),
+            retry_on_timeout: true,
+            fatal_patterns: Vec::new(),
+            transient_patterns: vec![
+                "ECONNRESET".to_string(),
+                "ETIMEDOUT".to_string(),
+                "ENOTFOUND".to_string(),
+                "net::ERR_".to_string(),
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".into());
+        }
+        if self.backoff_base_secs == 0 || self.backoff_max_secs < self.backoff_base_secs {
+            return Err("backoff_base_secs must be positive and no larger than backoff_max_secs".into());
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err("jitter must be between 0.0 and 1.0".into());
+        }
+        Ok(())
+    }
+
+    // `script` is the last script result of the failed run, if one ran at all.
+    // Failures raised before any script process started (bad task spec, missing
+    // agent, secret or login, spawn errors) and cancelled runs are never retried.
+    pub fn is_retryable(&self, script: Option<&OpenClawResult>) -> bool {
+        let Some(result) = script else {
+            return false;
+        };
+        if !result.started || result.cancelled {
+            return false;
+        }
+        let stderr = result.error.as_deref().unwrap_or("");
+        if self.fatal_patterns.iter().any(|p| stderr.contains(p.as_str())) {
+            return false;
+        }
+        if result.timed_out {
+            return self.retry_on_timeout;
+        }
+        if let Some(ScriptEvent::Error { retryable: Some(retryable), .. }) = result.script_error() {
+            return *retryable;
+        }
+        if self.transient_patterns.iter().any(|p| stderr.contains(p.as_str())) {
+            return true;
+        }
+        match result.exit_code {
+            Some(code) if self.retry_exit_codes.is_empty() => code != 0,
+            Some(code) => self.retry_exit_codes.contains(&code),
+            // Killed by a signal
+            None => true,
+        }
+    }
+
+    // Delay before attempt `attempt + 1`, where `attempt` failures have happened so far.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(30);
+        let base = self.backoff_base_secs.saturating_mul(1u64 << exp).min(self.backoff_max_secs) as f64;
+        let unit = OsRng.next_u32() as f64 / u32::MAX as f64;
+        let jittered = base * (1.0 + self.jitter * (2.0 * unit - 1.0));
+        Duration::from_secs_f64(jittered.max(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_processes_that_ran_are_retried() {
+        let policy = RetryPolicy::default();
+        let never_started = OpenClawResult::failed("No saved login for linkedin/default; log in from Settings first".into());
+        assert!(!policy.is_retryable(Some(&never_started)));
+
+        let killed = OpenClawResult { started: true, ..OpenClawResult::failed("".into()) };
+        assert!(policy.is_retryable(Some(&killed)));
+        assert!(!policy.is_retryable(Some(&OpenClawResult { cancelled: true, ..killed })));
+    }
+
+    #[test]
+    fn backoff_stays_within_the_jitter() {
+        let policy = RetryPolicy { backoff_base_secs: 100, jitter: 0.5, ..RetryPolicy::default() };
+        for _ in 0..50 {
+            let delay = policy.backoff(2).as_secs_f64();
+            assert!((100.0..=300.0).contains(&delay), "{}", delay);
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use crate::approvals::{self, APPROVALS_CHANGED_EVENT};
+use crate::db::{DbState, Agent, Approval, log_event};
+use crate::openclaw::{self, LineSink};
+use crate::policy::{self, ContentPolicy, Verdict};
+use crate::runs::{self, Trigger};
+use crate::scripts::Scripts;
+use crate::secrets::Vault;
+use crate::sessions::Sessions;
+use crate::stream::{RunSubscription, Streams};
+use crate::tasks::{self, Publication, TaskContext, TaskKind, TaskOutput, TaskSpec};
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use tokio::sync::{watch, Notify, OwnedSemaphorePermit, Semaphore};
+
+// Runs allowed at once across all agents unless the `max_concurrent_runs` setting says otherwise
+pub const DEFAULT_MAX_CONCURRENT_RUNS: usize = 2;
+
+pub(crate) fn resolve(agent: &Agent) -> std::result::Result<(TaskSpec, &'static dyn TaskKind), String> {
+    let spec = TaskSpec::parse(&agent.openclaw_task)?;
+    let kind = tasks::find(&spec.kind)
+        .ok_or_else(|| format!("No automation configured for agent '{}' (unknown task kind '{}')", agent.name, spec.kind))?;
+    Ok((spec, kind))
+}
+
+pub struct Execution {
+    pub run_id: i64,
+    pub outcome: std::result::Result<String, String>,
+}
+
+struct ActiveRun {
+    cancel: watch::Sender<bool>,
+    // Held until `end` has recorded the status, so the stream cannot close
+    // before it knows how the run finished
+    _output: LineSink,
+}
+
+// A reserved slot among an agent's `max_concurrency`; released on drop.
+pub struct Claim {
+    running: Arc<Mutex<HashMap<i32, usize>>>,
+    freed: Arc<Notify>,
+    agent_id: i32,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(count) = running.get_mut(&self.agent_id) {
+            *count -= 1;
+            if *count == 0 {
+                running.remove(&self.agent_id);
+            }
+        }
+        self.freed.notify_waiters();
+    }
+}
+
+// The worker pool. Shrinking never interrupts a busy worker: the slots that
+// are not free yet become debt, paid off as workers finish or by a later grow.
+#[derive(Clone)]
+struct Pool {
+    permits: Arc<Semaphore>,
+    size: Arc<Mutex<PoolSize>>,
+}
+
+struct PoolSize {
+    size: usize,
+    debt: usize,
+}
+
+// A busy worker slot; dropping it frees the slot unless a shrink claims it.
+struct Worker {
+    permit: Option<OwnedSemaphorePermit>,
+    size: Arc<Mutex<PoolSize>>,
+}
+
+impl Pool {
+    fn new(size: usize) -> Self {
+        Pool { permits: Arc::new(Semaphore::new(size)), size: Arc::new(Mutex::new(PoolSize { size, debt: 0 })) }
+    }
+
+    fn resize(&self, size: usize) {
+        let mut pool = self.size.lock().unwrap();
+        if size > pool.size {
+            let grow = size - pool.size;
+            let repaid = grow.min(pool.debt);
+            pool.debt -= repaid;
+            self.permits.add_permits(grow - repaid);
+        } else {
+            pool.debt += pool.size - size;
+            while pool.debt > 0 {
+                let Ok(permit) = self.permits.try_acquire() else { break };
+                permit.forget();
+                pool.debt -= 1;
+            }
+        }
+        pool.size = size;
+    }
+
+    async fn acquire(&self) -> std::result::Result<Worker, String> {
+        let permit = self.permits.clone().acquire_owned().await.map_err(|e| e.to_string())?;
+        Ok(Worker { permit: Some(permit), size: self.size.clone() })
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let mut pool = self.size.lock().unwrap();
+        if let Some(permit) = self.permit.take() {
+            if pool.debt > 0 {
+                pool.debt -= 1;
+                permit.forget();
+            }
+        }
+    }
+}
+
+// Executes agents and keeps track of the runs still in flight so they can be
+// cancelled. Shared by the scheduler and the Tauri commands. Runs go through
+// a worker pool of `max_concurrent_runs` slots and each agent is capped at its
+// own `max_concurrency`.
+#[derive(Clone)]
+pub struct Runner {
+    pub db: DbState,
+    pub vault: Vault,
+    pub scripts: Scripts,
+    pub sessions: Sessions,
+    pub streams: Streams,
+    // Idempotency keys scripts have published, see `tasks::Publication`
+    ledger: PathBuf,
+    active: Arc<Mutex<HashMap<i64, ActiveRun>>>,
+    pool: Pool,
+    running: Arc<Mutex<HashMap<i32, usize>>>,
+    // Woken whenever a `Claim` is released
+    freed: Arc<Notify>,
+}
+
+impl Runner {
+    pub fn new(db: DbState, vault: Vault, scripts: Scripts, sessions: Sessions, streams: Streams, ledger: PathBuf) -> Self {
+        let size = {
+            let conn = db.0.lock().unwrap();
+            conn.query_row("SELECT value FROM settings WHERE key = 'max_concurrent_runs'", [], |row| row.get::<_, String>(0))
+                .optional()
+                .ok()
+                .flatten()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_RUNS)
+        };
+        Runner {
+            db,
+            vault,
+            scripts,
+            sessions,
+            streams,
+            ledger,
+            active: Arc::new(Mutex::new(HashMap::new())),
+            pool: Pool::new(size),
+            running: Arc::new(Mutex::new(HashMap::new())),
+            freed: Arc::new(Notify::new()),
+        }
+    }
+
+    // Grows or shrinks the worker pool; see `Pool`.
+    pub fn set_pool_size(&self, size: usize) {
+        self.pool.resize(size);
+    }
+
+    pub fn claim(&self, agent: &Agent) -> std::result::Result<Claim, String> {
+        let limit = agent.max_concurrency.max(1) as usize;
+        let mut running = self.running.lock().unwrap();
+        let count = running.entry(agent.id).or_insert(0);
+        if *count >= limit {
+            return Err(format!("Agent '{}' is already running ({} of {} allowed)", agent.name, count, limit));
+        }
+        *count += 1;
+        Ok(Claim { running: self.running.clone(), freed: self.freed.clone(), agent_id: agent.id })
+    }
+
+    // Like `claim`, but waits for one of the agent's runs to finish instead of failing.
+    async fn wait_for_claim(&self, agent: &Agent) -> Claim {
+        loop {
+            let freed = self.freed.notified();
+            tokio::pin!(freed);
+            // Registered before trying, so a release in between is not missed
+            freed.as_mut().enable();
+            if let Ok(claim) = self.claim(agent) {
+                return claim;
+            }
+            freed.await;
+        }
+    }
+
+    // Waits for a free worker, then executes the agent on the blocking pool so
+    // the async workers stay free while scripts run. The run is recorded first,
+    // so it can be cancelled while it is still waiting. The error means the run
+    // could not even be recorded.
+    pub async fn dispatch(&self, claim: Claim, agent: Agent, dry_run: bool, trigger: Trigger) -> std::result::Result<Execution, String> {
+        let attempt = if trigger == Trigger::Scheduled { agent.retry_attempt + 1 } else { 1 };
+        let (run_id, mut cancel, output) = self.begin(agent.id, trigger, attempt, dry_run)?;
+        let permit = tokio::select! {
+            permit = self.pool.acquire() => permit,
+            _ = openclaw::cancelled(&mut cancel) => Err("Cancelled while waiting for a free worker".to_string()),
+        };
+        let permit = match permit {
+            Ok(permit) => permit,
+            Err(e) => {
+                let outcome = Err(e);
+                self.end(run_id, &outcome);
+                return Ok(Execution { run_id, outcome });
+            }
+        };
+        let runner = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let _permit = permit;
+            let _claim = claim;
+            runner.execute(&agent, dry_run, run_id, cancel, output)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    fn begin(&self, agent_id: i32, trigger: Trigger, attempt: i32, dry_run: bool) -> std::result::Result<(i64, watch::Receiver<bool>, LineSink), String> {
+        let run_id = {
+            let conn = self.db.0.lock().unwrap();
+            runs::start_run(&conn, agent_id, trigger, attempt, dry_run).map_err(|e| e.to_string())?
+        };
+        let (tx, rx) = watch::channel(false);
+        let output = self.streams.open(run_id, agent_id, trigger.as_str());
+        self.active.lock().unwrap().insert(run_id, ActiveRun { cancel: tx, _output: output.clone() });
+        Ok((run_id, rx, output))
+    }
+
+    fn end(&self, run_id: i64, outcome: &std::result::Result<String, String>) {
+        let run = self.active.lock().unwrap().remove(&run_id);
+        let cancelled = run.as_ref().is_some_and(|r| *r.cancel.borrow());
+        let conn = self.db.0.lock().unwrap();
+        if let Ok(status) = runs::finish_run(&conn, run_id, outcome, cancelled) {
+            self.streams.finish(run_id, status);
+        }
+    }
+
+    pub fn cancel(&self, run_id: i64) -> bool {
+        match self.active.lock().unwrap().get(&run_id) {
+            Some(run) => {
+                let _ = run.cancel.send(true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The one place an agent's work is carried out, whether the scheduler fired it
+    // or the user pressed "run now". A dry run goes all the way through publishing,
+    // but against the local mock targets, and never touches approvals.
+    // Blocks while scripts run, so call it from a blocking thread. The outer error
+    // means the run could not be marked on the agent.
+    fn execute(&self, agent: &Agent, dry_run: bool, run_id: i64, cancel: watch::Receiver<bool>, output: LineSink) -> std::result::Result<Execution, String> {
+        let state = &self.db;
+        {
+            let conn = state.0.lock().unwrap();
+            let _ = log_event(&conn, Some(agent.id), &format!("Processing agent: {}", agent.name), "Info");
+        }
+
+        let outcome = resolve(agent).and_then(|(spec, kind)| {
+            let ctx = TaskContext { state, vault: &self.vault, scripts: &self.scripts, sessions: &self.sessions, agent, spec: &spec, dry_run, run_id, publication: None, timeout: spec.timeout(kind), cancel, output };
+            match kind.execute(&ctx)? {
+                TaskOutput::Done(summary) => Ok(summary),
+                TaskOutput::Draft(content) => self.handle_draft(&ctx, kind, &content),
+            }
+        });
+
+        self.end(run_id, &outcome);
+
+        // Mark as run
+        if !dry_run {
+            let conn = state.0.lock().unwrap();
+            conn.execute(
+                "UPDATE agents SET last_run_at = CURRENT_TIMESTAMP WHERE id = ?",
+                [agent.id],
+            ).map_err(|e| e.to_string())?;
+        }
+
+        Ok(Execution { run_id, outcome })
+    }
+
+    // Checks a draft against the content policy for the platform it goes to.
+    fn screen(&self, ctx: &TaskContext, kind: &dyn TaskKind, content: &str) -> std::result::Result<Verdict, String> {
+        let conn = self.db.0.lock().unwrap();
+        let policy = ContentPolicy::load(&conn)?;
+        let violations = policy.evaluate(&conn, &kind.platform(ctx.spec), &kind.policy_text(content))?;
+        Ok(policy.verdict(violations))
+    }
+
+    // Publishes what went out and remembers it for the duplicate check.
+    fn publish(&self, ctx: &TaskContext, kind: &dyn TaskKind, content: &str) -> std::result::Result<String, String> {
+        let published = kind.publish(ctx, content);
+        if !ctx.dry_run {
+            let conn = self.db.0.lock().unwrap();
+            // A script that failed after its `published` checkpoint still put the content out
+            if published.is_ok() || runs::last_script_result(&conn, ctx.run_id).is_some_and(|r| r.published()) {
+                let _ = policy::record_published(&conn, ctx.agent.id, &kind.platform(ctx.spec), &kind.policy_text(content));
+            }
+        }
+        published
+    }
+
+    // A draft is screened by the content policy first. Drafts that pass are
+    // published, or held for approval when the kind requires it; policy
+    // violations either fail the run or hold the draft for approval as well.
+    // Dry runs record what the policy would have done and carry on to the mock.
+    fn handle_draft(&self, ctx: &TaskContext, kind: &dyn TaskKind, content: &str) -> std::result::Result<String, String> {
+        let agent = ctx.agent;
+        let verdict = self.screen(ctx, kind, content)?;
+        if let Verdict::Block(violations) = &verdict {
+            let reason = policy::describe(violations);
+            if ctx.dry_run {
+                ctx.capture("policy_block", Some(&kind.platform(ctx.spec)), &reason);
+            }
+            return Err(format!("Blocked by the content policy: {}", reason));
+        }
+        if ctx.dry_run {
+            if let Verdict::Hold(violations) = &verdict {
+                ctx.capture("policy_hold", Some(&kind.platform(ctx.spec)), &policy::describe(violations));
+            }
+            return kind.publish(ctx, content);
+        }
+
+        let violations = match verdict {
+            Verdict::Hold(violations) => violations,
+            _ if kind.requires_approval(ctx.spec, ctx.scripts) => Vec::new(),
+            _ => return self.publish(ctx, kind, content),
+        };
+        let conn = self.db.0.lock().unwrap();
+        approvals::create(&conn, agent, content, &violations).map_err(|e| e.to_string())?;
+        let message = if violations.is_empty() {
+            format!("Created approval for {}", agent.name)
+        } else {
+            format!("Created approval for {}; held by the content policy: {}", agent.name, policy::describe(&violations))
+        };
+        let _ = log_event(&conn, Some(agent.id), &message, "Info");
+        self.streams.emit(APPROVALS_CHANGED_EVENT, ());
+        Ok("approval requested".to_string())
+    }
+
+    // Posts an approved draft in the background. It waits until the agent has
+    // no other run going beyond its `max_concurrency` and for a worker like any
+    // other run, and the outcome ends up on the approval as Posted or Failed.
+    pub fn enqueue_approval(&self, approval: Approval) {
+        let runner = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let (id, agent_id) = (approval.id, approval.agent_id);
+            let started = async {
+                let agent = {
+                    let conn = runner.db.0.lock().unwrap();
+                    crate::db::get_agent(&conn, agent_id).map_err(|e| e.to_string())?
+                };
+                let claim = runner.wait_for_claim(&agent).await;
+                let permit = runner.pool.acquire().await?;
+                let worker = runner.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    let _permit = permit;
+                    let _claim = claim;
+                    worker.publish_approval(&approval)
+                })
+                .await
+                .map_err(|e| e.to_string())?
+            }
+            .await;
+            if let Err(e) = started {
+                let conn = runner.db.0.lock().unwrap();
+                let _ = approvals::settle(&conn, id, agent_id, &Err(e), None);
+            }
+            runner.streams.emit(APPROVALS_CHANGED_EVENT, ());
+        });
+    }
+
+    // Publishes the approved content through the task kind of the agent that
+    // drafted it and records the outcome. The error means no run was started.
+    // An approval that is already posted, being posted, or may have been posted
+    // is never published again, whatever state the approval itself is in.
+    fn publish_approval(&self, approval: &Approval) -> std::result::Result<(), String> {
+        let state = &self.db;
+        let key = approvals::idempotency_key(approval.id);
+        let agent = {
+            let conn = state.0.lock().unwrap();
+            let agent = crate::db::get_agent(&conn, approval.agent_id).map_err(|e| e.to_string())?;
+            if let Some(existing) = approvals::claim_publication(&conn, &key, approval.id).map_err(|e| e.to_string())? {
+                let _ = log_event(&conn, Some(agent.id), &format!("Refused to post approval {} again: it is already {}", approval.id, existing), "Error");
+                let _ = approvals::settle_existing(&conn, approval.id, &existing);
+                return Ok(());
+            }
+            agent
+        };
+        let (run_id, cancel, output) = self.begin(agent.id, Trigger::Approval, approval.attempts, false)?;
+        {
+            let conn = state.0.lock().unwrap();
+            let _ = approvals::link_run(&conn, approval.id, run_id);
+        }
+        self.streams.emit(APPROVALS_CHANGED_EVENT, ());
+
+        let outcome = resolve(&agent).and_then(|(spec, kind)| {
+            let ctx = TaskContext { state, vault: &self.vault, scripts: &self.scripts, sessions: &self.sessions, agent: &agent, spec: &spec, dry_run: false, run_id, publication: Some(Publication { key: key.clone(), ledger: self.ledger.clone() }), timeout: spec.timeout(kind), cancel, output };
+            // Someone approved it, so only violations that block still stop it
+            if let Verdict::Block(violations) = self.screen(&ctx, kind, &approval.content)? {
+                return Err(format!("Blocked by the content policy: {}", policy::describe(&violations)));
+            }
+            self.publish(&ctx, kind, &approval.content)
+        });
+
+        self.end(run_id, &outcome);
+        let conn = state.0.lock().unwrap();
+        let script = runs::last_script_result(&conn, run_id);
+        approvals::settle(&conn, approval.id, agent.id, &outcome, script.as_ref()).map_err(|e| e.to_string())
+    }
+
+    pub async fn run_agent_by_id(&self, id: i32, dry_run: bool) -> std::result::Result<String, String> {
+        let agent = {
+            let conn = self.db.0.lock().unwrap();
+            crate::db::get_agent(&conn, id).map_err(|e| e.to_string())?
+        };
+        let claim = self.claim(&agent)?;
+        self.dispatch(claim, agent, dry_run, Trigger::Manual).await?.outcome
+    }
+}
+
+#[tauri::command]
+pub fn cancel_run(runner: State<Runner>, id: i64) -> std::result::Result<(), String> {
+    if runner.cancel(id) {
+        Ok(())
+    } else {
+        Err(format!("Run {} is not in progress", id))
+    }
+}
+
+// Live output of a run still in progress: the channel it streams on and the
+// lines sent so far.
+#[tauri::command]
+pub fn subscribe_run(runner: State<Runner>, id: i64) -> std::result::Result<RunSubscription, String> {
+    runner.streams.subscribe(id).ok_or_else(|| format!("Run {} is not in progress", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+    use std::time::Duration;
+
+    fn free(pool: &Pool) -> usize {
+        pool.permits.available_permits()
+    }
+
+    #[test]
+    fn a_grow_pays_off_a_pending_shrink_first() {
+        tauri::async_runtime::block_on(async {
+            let pool = Pool::new(2);
+            let (a, b) = (pool.acquire().await.unwrap(), pool.acquire().await.unwrap());
+            pool.resize(1);
+            pool.resize(3);
+            drop((a, b));
+            assert_eq!(free(&pool), 3);
+        });
+    }
+
+    #[test]
+    fn a_shrink_waits_for_busy_workers() {
+        tauri::async_runtime::block_on(async {
+            let pool = Pool::new(3);
+            let busy = pool.acquire().await.unwrap();
+            pool.resize(1);
+            assert_eq!(free(&pool), 0);
+            drop(busy);
+            assert_eq!(free(&pool), 1);
+            pool.resize(2);
+            assert_eq!(free(&pool), 2);
+        });
+    }
+
+    #[test]
+    fn an_approved_post_waits_for_a_running_scheduled_run() {
+        let dir = std::env::temp_dir().join(format!("personaliz-runner-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run(&mut conn, None).unwrap();
+        conn.execute("INSERT INTO agents (name, openclaw_task, schedule) VALUES ('poster', '{\"kind\":\"trending_post\"}', '0 * * * *')", []).unwrap();
+        let agent = crate::db::get_agent(&conn, 1).unwrap();
+        let approval_id = approvals::create(&conn, &agent, "Hello", &[]).unwrap() as i32;
+        let approval = approvals::get(&conn, approval_id).unwrap().unwrap();
+        let vault = Vault::open(&conn, dir.join("vault.key")).unwrap();
+        let db = DbState(Arc::new(Mutex::new(conn)));
+        let runner = Runner::new(db.clone(), vault, Scripts::new(dir.clone(), dir.clone()), Sessions::new(dir.join("sessions")), Streams::new(None), dir.join("published_keys"));
+        let approval_runs = || db.0.lock().unwrap().query_row("SELECT COUNT(*) FROM runs WHERE trigger = 'Approval'", [], |row| row.get::<_, i64>(0)).unwrap();
+
+        tauri::async_runtime::block_on(async {
+            // The scheduler holds the agent's only slot while its run goes on
+            let scheduled = runner.claim(&agent).unwrap();
+            runner.enqueue_approval(approval);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            assert_eq!(approval_runs(), 0);
+
+            drop(scheduled);
+            for _ in 0..50 {
+                if approval_runs() == 1 {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            assert_eq!(approval_runs(), 1);
+        });
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use crate::db::DbState;
+use crate::openclaw::{Capture, OpenClawResult};
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trigger {
+    Scheduled,
+    Manual,
+    Approval,
+}
+
+impl Trigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Trigger::Scheduled => "Scheduled",
+            Trigger::Manual => "Manual",
+            Trigger::Approval => "Approval",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Run {
+    pub id: i64,
+    pub agent_id: i32,
+    pub trigger: String, // Scheduled, Manual, Approval
+    pub attempt: i32,
+    pub status: String,  // Running, Succeeded, Failed, Cancelled
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub exit_code: Option<i32>,
+    pub summary: Option<String>,
+    pub dry_run: bool,
+    // What a dry run would have posted or commented
+    pub captured: Vec<Capture>,
+    // Only filled in by `get_run`; listings leave the bulky fields out
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+    pub result: Option<serde_json::Value>,
+}
+
+fn run_from_row(row: &rusqlite::Row) -> Result<Run> {
+    let result: Option<String> = row.get(10)?;
+    let captured: Option<String> = row.get(13)?;
+    Ok(Run {
+        id: row.get(0)?,
+        agent_id: row.get(1)?,
+        trigger: row.get(2)?,
+        attempt: row.get(11)?,
+        status: row.get(3)?,
+        started_at: row.get(4)?,
+        finished_at: row.get(5)?,
+        exit_code: row.get(6)?,
+        summary: row.get(7)?,
+        dry_run: row.get(12)?,
+        captured: captured.and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default(),
+        stdout: row.get(8)?,
+        stderr: row.get(9)?,
+        result: result.and_then(|r| serde_json::from_str(&r).ok()),
+    })
+}
+
+pub fn start_run(conn: &Connection, agent_id: i32, trigger: Trigger, attempt: i32, dry_run: bool) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO runs (agent_id, trigger, attempt, dry_run, status, started_at) VALUES (?, ?, ?, ?, 'Running', CURRENT_TIMESTAMP)",
+        params![agent_id, trigger.as_str(), attempt, dry_run],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn record_captures(conn: &Connection, run_id: i64, captures: &[Capture]) -> Result<()> {
+    if captures.is_empty() {
+        return Ok(());
+    }
+    let existing: Option<String> = conn.query_row("SELECT captured_json FROM runs WHERE id = ?", [run_id], |row| row.get(0))?;
+    let mut all: Vec<Capture> = existing.and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+    all.extend_from_slice(captures);
+    conn.execute(
+        "UPDATE runs SET captured_json = ? WHERE id = ?",
+        params![serde_json::to_string(&all).unwrap_or_default(), run_id],
+    )?;
+    Ok(())
+}
+
+// Appends a script's output to its run; a run that calls several scripts keeps
+// all of their stdout/stderr and the last structured result.
+pub fn record_script(conn: &Connection, run_id: i64, result: &OpenClawResult) -> Result<()> {
+    let payload = serde_json::to_string(result).unwrap_or_default();
+    conn.execute(
+        "UPDATE runs SET
+            stdout = COALESCE(stdout, '') || ?,
+            stderr = COALESCE(stderr, '') || ?,
+            exit_code = ?,
+            result_json = ?
+         WHERE id = ?",
+        params![result.output, result.error.as_deref().unwrap_or(""), result.exit_code, payload, run_id],
+    )?;
+    Ok(())
+}
+
+// The structured result of the last script the run executed, if any ran.
+pub fn last_script_result(conn: &Connection, run_id: i64) -> Option<OpenClawResult> {
+    conn.query_row("SELECT result_json FROM runs WHERE id = ?", [run_id], |row| row.get::<_, Option<String>>(0))
+        .ok()
+        .flatten()
+        .and_then(|r| serde_json::from_str(&r).ok())
+}
+
+pub fn finish_run(conn: &Connection, run_id: i64, outcome: &std::result::Result<String, String>, cancelled: bool) -> Result<&'static str> {
+    let (status, summary) = match outcome {
+        Ok(summary) => ("Succeeded", summary),
+        Err(error) if cancelled => ("Cancelled", error),
+        Err(error) => ("Failed", error),
+    };
+    conn.execute(
+        "UPDATE runs SET status = ?, summary = ?, finished_at = CURRENT_TIMESTAMP WHERE id = ?",
+        params![status, summary, run_id],
+    )?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn list_runs(
+    state: State<DbState>,
+    agent_id: Option<i32>,
+    trigger: Option<String>,
+    status: Option<String>,
+    dry_run: Option<bool>,
+    limit: Option<u32>,
+) -> std::result::Result<Vec<Run>, String> {
+    let conn = state.0.lock().unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, agent_id, trigger, status, started_at, finished_at, exit_code, summary, NULL, NULL, NULL, attempt, dry_run, captured_json
+             FROM runs
+             WHERE (?1 IS NULL OR agent_id = ?1)
+               AND (?2 IS NULL OR trigger = ?2)
+               AND (?3 IS NULL OR status = ?3)
+               AND (?4 IS NULL OR dry_run = ?4)
+             ORDER BY started_at DESC, id DESC
+             LIMIT ?5",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![agent_id, trigger, status, dry_run, limit.unwrap_or(100)], run_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_run(state: State<DbState>, id: i64) -> std::result::Result<Run, String> {
+    let conn = state.0.lock().unwrap();
+    conn.query_row(
+        "SELECT id, agent_id, trigger, status, started_at, finished_at, exit_code, summary, stdout, stderr, result_json, attempt, dry_run, captured_json
+         FROM runs WHERE id = ?",
+        [id],
+        run_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
@@ -1,67 +1,471 @@
+use chrono::{DateTime, Local, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::str::FromStr;
+use std::time::Instant;
 use tokio::time::{sleep, Duration};
-use crate::db::{DbState, Agent, log_event};
+use crate::approvals;
+use crate::db::{self, DbState, Agent, log_event};
+use crate::retry::RetryPolicy;
+use crate::runner::{Execution, Runner};
+use crate::runs::{self, Trigger};
 
-pub async fn start_scheduler(state: DbState) {
-    loop {
-        let agents = {
-            let conn = state.0.lock().unwrap();
-            let mut stmt = conn.prepare("SELECT id, name, description, openclaw_task, schedule, status, last_run_at, next_run_at FROM agents WHERE status = 'Active'").unwrap();
-            let agent_iter = stmt.query_map([], |row| {
-                Ok(Agent {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2).unwrap_or_default(),
-                    openclaw_task: row.get(3)?,
-                    schedule: row.get(4).unwrap_or_default(),
-                    status: row.get(5)?,
-                    last_run_at: row.get(6).ok(),
-                    next_run_at: row.get(7).ok(),
-                })
-            }).unwrap();
-
-            let mut list = Vec::new();
-            for agent in agent_iter {
-                list.push(agent.unwrap());
-            }
-            list
+const TICK: Duration = Duration::from_secs(30);
+// A run that starts later than this after its slot counts as missed
+const MISFIRE_GRACE: chrono::Duration = chrono::Duration::minutes(2);
+// Wall clock moving this much more (or less) than the monotonic clock between
+// ticks means the machine slept or the system time was changed
+const CLOCK_JUMP: chrono::Duration = chrono::Duration::minutes(1);
+
+// What to do with slots that passed while the app was closed or the machine
+// slept. Stored as JSON in `agents.misfire_policy`, e.g. {"policy":"run_all","max_runs":3}.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    // Drop the missed slots and wait for the next regular one
+    Skip,
+    // Run once for all missed slots, then carry on with the regular schedule
+    #[default]
+    RunOnce,
+    // Run every missed slot, oldest first, but no more than `max_runs` of them
+    RunAll { max_runs: u32 },
+}
+
+impl MisfirePolicy {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        match self {
+            MisfirePolicy::RunAll { max_runs: 0 } => Err("max_runs must be at least 1".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Accepts 5-field crontab lines, the cron crate's native 6/7-field form and the
+// "Manual"/"Hourly"/"Daily"/"Weekly" shorthands the chat flow creates.
+// Ok(None) means the agent only runs when triggered by hand.
+pub fn parse_schedule(expr: &str) -> std::result::Result<Option<Schedule>, String> {
+    let trimmed = expr.trim();
+    let normalized = match trimmed.to_lowercase().as_str() {
+        "" | "manual" => return Ok(None),
+        "hourly" => "0 0 * * * *".to_string(),
+        // Matches the 09:00 default of the original demo agents
+        "daily" => "0 0 9 * * *".to_string(),
+        "weekly" => "0 0 9 * * Mon".to_string(),
+        _ if trimmed.split_whitespace().count() == 5 => {
+            let fields: Vec<&str> = trimmed.split_whitespace().collect();
+            let days = crontab_weekdays(fields[4]).map_err(|e| format!("Invalid schedule '{}': {}", expr, e))?;
+            format!("0 {} {} {} {} {}", fields[0], fields[1], fields[2], fields[3], days)
+        }
+        _ => trimmed.to_string(),
+    };
+    Schedule::from_str(&normalized)
+        .map(Some)
+        .map_err(|e| format!("Invalid schedule '{}': {}", expr, e))
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+// Crontab numbers weekdays 0-7 with Sunday as both 0 and 7, the cron crate 1-7
+// from Sunday. Rewrites the field as the list of day names it selects.
+fn crontab_weekdays(field: &str) -> std::result::Result<String, String> {
+    if field == "*" || field == "?" {
+        return Ok(field.to_string());
+    }
+    let day = |s: &str| -> std::result::Result<usize, String> {
+        WEEKDAYS
+            .iter()
+            .position(|d| d.eq_ignore_ascii_case(s))
+            .or_else(|| s.parse::<usize>().ok().filter(|n| *n <= 7))
+            .ok_or_else(|| format!("'{}' is not a day of the week", s))
+    };
+    let mut selected = [false; 7];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<usize>() {
+                Ok(n) if n > 0 => (range, n),
+                _ => return Err(format!("'{}' is not a valid step", step)),
+            },
+            None => (part, 1),
         };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (0, 6),
+            Some((first, last)) => (day(first)?, day(last)?),
+            // "1/2" steps from Monday to the end of the week
+            None if part.contains('/') => (day(range)?, 6),
+            None => (day(range)?, day(range)?),
+        };
+        if first > last {
+            return Err(format!("day range '{}' runs backwards", range));
+        }
+        for d in (first..=last).step_by(step) {
+            selected[d % 7] = true;
+        }
+    }
+    Ok(WEEKDAYS.iter().zip(selected).filter(|(_, on)| *on).map(|(d, _)| *d).collect::<Vec<_>>().join(","))
+}
 
-        for agent in agents {
-            {
-                let conn = state.0.lock().unwrap();
-                let _ = log_event(&conn, Some(agent.id), &format!("Processing agent: {}", agent.name), "Info");
+// Cron expressions are written in the user's wall-clock time, persisted in UTC.
+pub fn next_run_after(schedule: &Schedule, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    schedule
+        .after(&after.with_timezone(&Local))
+        .next()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// The `next_run_at` value to store for a freshly created or edited agent.
+pub fn initial_next_run(schedule: &str) -> std::result::Result<Option<String>, String> {
+    Ok(parse_schedule(schedule)?
+        .and_then(|s| next_run_after(&s, Utc::now()))
+        .map(db::format_timestamp))
+}
+
+fn load_active_agents(state: &DbState) -> Vec<Agent> {
+    let conn = state.0.lock().unwrap();
+    db::list_agents(&conn, Some("Active")).unwrap_or_default()
+}
+
+fn set_next_run(state: &DbState, agent_id: i32, next: Option<DateTime<Utc>>) {
+    let conn = state.0.lock().unwrap();
+    let _ = conn.execute(
+        "UPDATE agents SET next_run_at = ? WHERE id = ?",
+        rusqlite::params![next.map(db::format_timestamp), agent_id],
+    );
+}
+
+fn mark_schedule_error(state: &DbState, agent: &Agent, error: &str) {
+    let conn = state.0.lock().unwrap();
+    let _ = conn.execute(
+        "UPDATE agents SET status = 'Error', next_run_at = NULL, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        [agent.id],
+    );
+    let _ = log_event(&conn, Some(agent.id), &format!("Agent '{}' disabled: {}", agent.name, error), "Error");
+}
+
+// Slots from `due` up to `now` that never ran: how many there were and the
+// oldest of the most recent `keep`.
+fn missed_slots(schedule: &Schedule, due: DateTime<Utc>, now: DateTime<Utc>, keep: usize) -> (usize, DateTime<Utc>) {
+    let mut total = 1;
+    let mut kept = VecDeque::from([due]);
+    let later = schedule
+        .after(&due.with_timezone(&Local))
+        .map(|t| t.with_timezone(&Utc))
+        .take_while(|t| *t <= now);
+    for slot in later {
+        total += 1;
+        kept.push_back(slot);
+        if kept.len() > keep.max(1) {
+            kept.pop_front();
+        }
+    }
+    (total, kept[0])
+}
+
+// Decides what happens after a scheduled attempt: on to `following` on
+// success, another attempt after a backoff for transient failures, or give
+// up once the policy is exhausted. When the next regular occurrence comes
+// before the retry would, the failed one is dropped in its favour. During a catch-up `following` is a missed slot that is already
+// past; it is kept in `catch_up_at` and the catch-up resumes there once the
+// retries settle.
+fn settle_attempt(
+    state: &DbState,
+    agent: &Agent,
+    schedule: &Schedule,
+    following: Option<DateTime<Utc>>,
+    execution: std::result::Result<Execution, String>,
+) {
+    let policy = agent.retry_policy.clone().unwrap_or_default();
+    let attempt = agent.retry_attempt + 1;
+    let now = Utc::now();
+    let regular = next_run_after(schedule, now);
+
+    let conn = state.0.lock().unwrap();
+    let (error, script) = match execution {
+        Ok(Execution { outcome: Ok(_), .. }) => {
+            let _ = conn.execute(
+                "UPDATE agents SET retry_attempt = 0, last_run_status = 'Succeeded', next_run_at = ?, catch_up_at = NULL WHERE id = ?",
+                rusqlite::params![following.map(db::format_timestamp), agent.id],
+            );
+            return;
+        }
+        Ok(Execution { run_id, outcome: Err(e) }) => (e, runs::last_script_result(&conn, run_id)),
+        Err(e) => (e, None),
+    };
+
+    let retryable = policy.is_retryable(script.as_ref());
+    let mut next = following;
+    let mut superseded = false;
+    if retryable && (attempt as u32) < policy.max_attempts {
+        let retry_at = now + backoff_delay(&policy, attempt);
+        let catch_up = following.filter(|f| *f <= now);
+        match regular {
+            // The next regular slot is a fresh occurrence, not another attempt at this one
+            Some(regular) if catch_up.is_none() && regular <= retry_at => {
+                next = Some(regular);
+                superseded = true;
+            }
+            _ => {
+                let _ = conn.execute(
+                    "UPDATE agents SET retry_attempt = ?, last_run_status = 'Retrying', next_run_at = ?, catch_up_at = ? WHERE id = ?",
+                    rusqlite::params![attempt, db::format_timestamp(retry_at), catch_up.map(db::format_timestamp), agent.id],
+                );
+                let _ = log_event(
+                    &conn,
+                    Some(agent.id),
+                    &format!("Scheduled run failed (attempt {}/{}), retrying at {}: {}", attempt, policy.max_attempts, db::format_timestamp(retry_at), error),
+                    "Error",
+                );
+                return;
             }
-            
-            if agent.name.contains("Trending") {
-                // Demo 1: Search trends and create approval
-                let trend = crate::openclaw::search_trends();
+        }
+    }
+
+    let (status, message) = if superseded {
+        ("Failed", format!("Scheduled run failed and the next regular run comes before a retry would: {}", error))
+    } else if retryable {
+        ("GaveUp", format!("Scheduled run gave up after {} attempts: {}", attempt, error))
+    } else {
+        ("Failed", format!("Scheduled run failed: {}", error))
+    };
+    let _ = conn.execute(
+        "UPDATE agents SET retry_attempt = 0, last_run_status = ?, next_run_at = ?, catch_up_at = NULL WHERE id = ?",
+        rusqlite::params![status, next.map(db::format_timestamp), agent.id],
+    );
+    let _ = log_event(&conn, Some(agent.id), &message, "Error");
+}
+
+fn backoff_delay(policy: &RetryPolicy, attempt: i32) -> chrono::Duration {
+    chrono::Duration::from_std(policy.backoff(attempt as u32)).unwrap_or_else(|_| chrono::Duration::seconds(policy.backoff_max_secs as i64))
+}
+
+pub async fn start_scheduler(runner: Runner) {
+    let state = runner.db.clone();
+    // Agents whose scheduled run is still going; they are not fired again until it settles
+    let in_flight: Arc<Mutex<HashSet<i32>>> = Arc::new(Mutex::new(HashSet::new()));
+    let mut last_tick: Option<(DateTime<Utc>, Instant)> = None;
+    loop {
+        // Slots missed while the app was closed are picked up by the misfire
+        // handling below on the first pass; sleep and clock changes are noticed here.
+        let mut clock_moved_back = false;
+        if let Some((wall, mono)) = last_tick {
+            let drift = (Utc::now() - wall) - chrono::Duration::from_std(mono.elapsed()).unwrap_or_default();
+            if drift.abs() > CLOCK_JUMP {
+                clock_moved_back = drift < chrono::Duration::zero();
                 let conn = state.0.lock().unwrap();
-                conn.execute(
-                    "INSERT INTO approvals (agent_id, content) VALUES (?, ?)",
-                    rusqlite::params![agent.id, trend],
-                ).unwrap();
-                let _ = log_event(&conn, Some(agent.id), "Created approval for Trending Agent", "Info");
-            } else if agent.name.contains("Hashtag") {
-                // Demo 2: Run comment script directly
-                {
-                    let conn = state.0.lock().unwrap();
-                    let _ = log_event(&conn, Some(agent.id), "Running Hashtag Agent script...", "Info");
+                let _ = log_event(
+                    &conn,
+                    None,
+                    &format!("Clock jumped {} by {}s; re-evaluating schedules", if clock_moved_back { "back" } else { "forward" }, drift.num_seconds().abs()),
+                    "Info",
+                );
+            }
+        }
+        last_tick = Some((Utc::now(), Instant::now()));
+
+        approvals::sweep(&state, &runner.streams);
+
+        let agents = load_active_agents(&state);
+
+        for agent in agents {
+            let schedule = match parse_schedule(&agent.schedule) {
+                Ok(Some(schedule)) => schedule,
+                Ok(None) => {
+                    // Manual agents never carry a pending run
+                    if agent.next_run_at.is_some() {
+                        set_next_run(&state, agent.id, None);
+                    }
+                    continue;
                 }
-                let result = crate::openclaw::run_script("linkedin_comment.cjs", "#openclaw");
-                
-                let conn = state.0.lock().unwrap();
-                let _ = log_event(&conn, Some(agent.id), &format!("Hashtag agent script finished. Result: {:?}", result), "Info");
-                let _ = log_event(&conn, Some(agent.id), "Commented on LinkedIn posts", "Info");
+                Err(e) => {
+                    mark_schedule_error(&state, &agent, &e);
+                    continue;
+                }
+            };
+
+            let now = Utc::now();
+            let regular = next_run_after(&schedule, now);
+            let due = match agent.next_run_at.as_deref().and_then(db::parse_timestamp) {
+                Some(due) if due <= now => due,
+                // Planned against a clock that has since been set back
+                Some(due) if clock_moved_back && regular.is_some_and(|r| due > r) => {
+                    set_next_run(&state, agent.id, regular);
+                    continue;
+                }
+                Some(_) => continue,
+                // First time we see this agent (or the column was cleared): plan, don't fire
+                None => {
+                    set_next_run(&state, agent.id, regular);
+                    continue;
+                }
+            };
+
+            if in_flight.lock().unwrap().contains(&agent.id) {
+                continue;
             }
-            
-            // Mark as run
-            let conn = state.0.lock().unwrap();
-            conn.execute(
-                "UPDATE agents SET last_run_at = CURRENT_TIMESTAMP WHERE id = ?",
-                [agent.id],
-            ).unwrap();
+            // Busy with manual runs up to its limit: leave it due and look again next tick
+            let Ok(claim) = runner.claim(&agent) else {
+                continue;
+            };
+
+            // A retry in the middle of a catch-up goes back to the catch-up afterwards
+            let catch_up = agent.catch_up_at.as_deref().and_then(db::parse_timestamp).filter(|_| agent.retry_attempt > 0);
+            let following = if catch_up.is_some() {
+                catch_up
+            } else if now - due <= MISFIRE_GRACE {
+                regular
+            } else {
+                match agent.misfire_policy {
+                    MisfirePolicy::Skip => {
+                        let (missed, _) = missed_slots(&schedule, due, now, 1);
+                        let conn = state.0.lock().unwrap();
+                        let _ = conn.execute(
+                            "UPDATE agents SET next_run_at = ?, retry_attempt = 0 WHERE id = ?",
+                            rusqlite::params![regular.map(db::format_timestamp), agent.id],
+                        );
+                        let _ = log_event(&conn, Some(agent.id), &format!("Skipped {} missed run(s) since {}", missed, db::format_timestamp(due)), "Info");
+                        continue;
+                    }
+                    MisfirePolicy::RunOnce => {
+                        let (missed, _) = missed_slots(&schedule, due, now, 1);
+                        let conn = state.0.lock().unwrap();
+                        let _ = log_event(&conn, Some(agent.id), &format!("Catching up once for {} missed run(s) since {}", missed, db::format_timestamp(due)), "Info");
+                        regular
+                    }
+                    MisfirePolicy::RunAll { max_runs } => {
+                        let (missed, oldest) = missed_slots(&schedule, due, now, max_runs as usize);
+                        let conn = state.0.lock().unwrap();
+                        if missed > max_runs as usize {
+                            let _ = log_event(&conn, Some(agent.id), &format!("Skipped {} missed run(s); catching up on the last {}", missed - max_runs as usize, max_runs), "Info");
+                        }
+                        let _ = log_event(&conn, Some(agent.id), &format!("Catching up missed run from {}", db::format_timestamp(oldest)), "Info");
+                        // Anything after this slot that is already past fires on the next tick
+                        next_run_after(&schedule, oldest)
+                    }
+                }
+            };
+
+            in_flight.lock().unwrap().insert(agent.id);
+            let worker = runner.clone();
+            let state = state.clone();
+            let in_flight = in_flight.clone();
+            // Each due run waits for a worker on its own so a slow script never holds up other agents
+            tauri::async_runtime::spawn(async move {
+                let execution = worker.dispatch(claim, agent.clone(), agent.dry_run, Trigger::Scheduled).await;
+                settle_attempt(&state, &agent, &schedule, following, execution);
+                in_flight.lock().unwrap().remove(&agent.id);
+            });
         }
 
-        sleep(Duration::from_secs(60)).await;
+        sleep(TICK).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::openclaw::OpenClawResult;
+    use rusqlite::Connection;
+
+    fn setup() -> (DbState, Agent) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run(&mut conn, None).unwrap();
+        conn.execute(
+            "INSERT INTO agents (name, openclaw_task, schedule, misfire_policy) VALUES ('poster', '{\"kind\":\"trending_post\"}', '0 * * * *', '{\"policy\":\"run_all\",\"max_runs\":3}')",
+            [],
+        )
+        .unwrap();
+        let agent = db::get_agent(&conn, 1).unwrap();
+        (DbState(Arc::new(Mutex::new(conn))), agent)
+    }
+
+    // A scheduled attempt that timed out, which the default policy retries
+    fn timed_out(state: &DbState) -> std::result::Result<Execution, String> {
+        let conn = state.0.lock().unwrap();
+        let run_id = runs::start_run(&conn, 1, Trigger::Scheduled, 1, false).unwrap();
+        let result = OpenClawResult { timed_out: true, started: true, ..OpenClawResult::failed("Timed out after 300s".into()) };
+        runs::record_script(&conn, run_id, &result).unwrap();
+        Ok(Execution { run_id, outcome: Err("Timed out after 300s".into()) })
+    }
+
+    fn weekdays(expr: &str) -> Vec<chrono::Weekday> {
+        use chrono::{Datelike, TimeZone};
+        let schedule = parse_schedule(expr).unwrap().unwrap();
+        let mut days: Vec<chrono::Weekday> = schedule.after(&Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap()).take(7).map(|t| t.weekday()).collect();
+        days.sort_by_key(|d| d.num_days_from_sunday());
+        days.dedup();
+        days
+    }
+
+    #[test]
+    fn crontab_weekdays_count_from_sunday_zero() {
+        use chrono::Weekday::*;
+        assert_eq!(weekdays("0 9 * * 1-5"), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(weekdays("0 9 * * 0"), [Sun]);
+        assert_eq!(weekdays("0 9 * * 7"), [Sun]);
+        assert_eq!(weekdays("0 9 * * 5-7"), [Sun, Fri, Sat]);
+        assert_eq!(weekdays("0 9 * * 1,3,5"), [Mon, Wed, Fri]);
+        assert_eq!(weekdays("0 9 * * */2"), [Sun, Tue, Thu, Sat]);
+        assert_eq!(weekdays("0 9 * * mon-fri/2"), [Mon, Wed, Fri]);
+        assert!(parse_schedule("0 9 * * 8").is_err());
+        assert!(parse_schedule("0 9 * * 5-1").is_err());
+    }
+
+    #[test]
+    fn shorthands_map_to_fixed_schedules() {
+        use chrono::{Datelike, Timelike, TimeZone};
+        let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 30, 0).unwrap();
+        let first = |expr: &str| parse_schedule(expr).unwrap().unwrap().after(&start).next().unwrap();
+        assert_eq!(first("Hourly"), Utc.with_ymd_and_hms(2026, 1, 1, 1, 0, 0).unwrap());
+        assert_eq!((first("daily").hour(), first("daily").minute()), (9, 0));
+        assert_eq!(first("Weekly").weekday(), chrono::Weekday::Mon);
+        assert_eq!(first("Weekly").hour(), 9);
+        assert!(parse_schedule("Manual").unwrap().is_none());
+        assert!(parse_schedule("").unwrap().is_none());
+    }
+
+    #[test]
+    fn a_retry_keeps_the_pending_catch_up_slot() {
+        let (state, agent) = setup();
+        let schedule = parse_schedule(&agent.schedule).unwrap().unwrap();
+        let owed = next_run_after(&schedule, Utc::now() - chrono::Duration::hours(3)).unwrap();
+
+        settle_attempt(&state, &agent, &schedule, Some(owed), timed_out(&state));
+        let agent = db::get_agent(&state.0.lock().unwrap(), 1).unwrap();
+        assert_eq!(agent.retry_attempt, 1);
+        assert_eq!(agent.catch_up_at, Some(db::format_timestamp(owed)));
+        assert!(agent.next_run_at.as_deref().and_then(db::parse_timestamp).unwrap() > Utc::now());
+
+        let success = Ok(Execution { run_id: 2, outcome: Ok("done".into()) });
+        settle_attempt(&state, &agent, &schedule, Some(owed), success);
+        let agent = db::get_agent(&state.0.lock().unwrap(), 1).unwrap();
+        assert_eq!(agent.next_run_at, Some(db::format_timestamp(owed)));
+        assert_eq!(agent.catch_up_at, None);
+    }
+
+    #[test]
+    fn a_retry_outside_a_catch_up_stops_at_the_next_regular_slot() {
+        let (state, agent) = setup();
+        let schedule = parse_schedule(&agent.schedule).unwrap().unwrap();
+        let regular = next_run_after(&schedule, Utc::now()).unwrap();
+
+        settle_attempt(&state, &agent, &schedule, Some(regular), timed_out(&state));
+        let agent = db::get_agent(&state.0.lock().unwrap(), 1).unwrap();
+        assert_eq!(agent.catch_up_at, None);
+        assert!(agent.next_run_at.as_deref().and_then(db::parse_timestamp).unwrap() <= regular);
+    }
+
+    #[test]
+    fn a_regular_slot_before_the_retry_starts_over_at_attempt_one() {
+        let (state, mut agent) = setup();
+        agent.retry_policy = Some(RetryPolicy { backoff_base_secs: 7200, backoff_max_secs: 7200, jitter: 0.0, ..RetryPolicy::default() });
+        let schedule = parse_schedule(&agent.schedule).unwrap().unwrap();
+        let regular = next_run_after(&schedule, Utc::now()).unwrap();
+
+        settle_attempt(&state, &agent, &schedule, Some(regular), timed_out(&state));
+        let agent = db::get_agent(&state.0.lock().unwrap(), 1).unwrap();
+        assert_eq!(agent.retry_attempt, 0);
+        assert_eq!(agent.last_run_status.as_deref(), Some("Failed"));
+        assert_eq!(agent.next_run_at, Some(db::format_timestamp(regular)));
     }
 }
@@ -0,0 +1,150 @@
+use crate::db::{DbState, log_event};
+use crate::{plugins, tasks};
+use serde::Serialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tauri::State;
+use which::which;
+
+// Shared by every bundled script, so it must be present even when no agent uses it directly
+const HELPERS: &[&str] = &["protocol.cjs", "login.cjs"];
+
+// Automation scripts ship as Tauri resources (`scripts/` in the resource dir).
+// A script with the same file name in the override dir (`scripts/` in the app
+// data dir) takes precedence, so users can patch or replace one without
+// rebuilding the app. Node packages such as Playwright are looked up in the
+// `node_modules` of either directory.
+#[derive(Clone, Debug)]
+pub struct Scripts {
+    pub bundled: PathBuf,
+    pub overrides: PathBuf,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ScriptHealth {
+    pub bundled_dir: String,
+    pub override_dir: String,
+    pub node_version: Option<String>,
+    pub has_playwright: bool,
+    // Scripts and plugins that agents need but neither directory has
+    pub missing: Vec<String>,
+    // One line per problem, ready to show the user
+    pub errors: Vec<String>,
+}
+
+impl Scripts {
+    pub fn new(bundled: PathBuf, overrides: PathBuf) -> Self {
+        Scripts { bundled, overrides }
+    }
+
+    pub fn resolve(&self, name: &str) -> Result<PathBuf, String> {
+        // Only bare file names; anything else could reach outside the script dirs
+        if name.is_empty() || Path::new(name).file_name().and_then(|n| n.to_str()) != Some(name) {
+            return Err(format!("Invalid script name '{}'", name));
+        }
+        [&self.overrides, &self.bundled]
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| format!("Script '{}' not found in {} or {}", name, self.overrides.display(), self.bundled.display()))
+    }
+
+    // Local stand-ins for the sites scripts publish to, used by dry runs.
+    pub fn mock_dir(&self) -> PathBuf {
+        let overrides = self.overrides.join("mock");
+        if overrides.is_dir() { overrides } else { self.bundled.join("mock") }
+    }
+
+    // NODE_PATH for script runs, so `require('playwright')` works from either
+    // directory and plugins can `require('protocol.cjs')` wherever they live.
+    pub fn node_path(&self) -> OsString {
+        let mut dirs = vec![
+            self.overrides.join("node_modules"),
+            self.bundled.join("node_modules"),
+            self.overrides.clone(),
+            self.bundled.clone(),
+        ];
+        if let Some(existing) = std::env::var_os("NODE_PATH") {
+            dirs.extend(std::env::split_paths(&existing));
+        }
+        std::env::join_paths(dirs).unwrap_or_default()
+    }
+
+    // Helper scripts and the bundled plugins the task kinds run that cannot be found
+    fn missing(&self) -> Vec<String> {
+        let mut plugins: Vec<&str> = tasks::kinds().iter().flat_map(|k| k.plugins()).copied().collect();
+        plugins.sort_unstable();
+        plugins.dedup();
+        let helpers = HELPERS.iter().filter(|name| self.resolve(name).is_err()).map(|name| name.to_string());
+        let plugins = plugins.into_iter().filter(|name| plugins::find(self, name).is_err()).map(|name| format!("plugins/{}", name));
+        helpers.chain(plugins).collect()
+    }
+
+    // Blocks on `node`; call it off the async runtime.
+    pub fn check(&self) -> ScriptHealth {
+        let mut errors = Vec::new();
+
+        let missing = self.missing();
+        if !missing.is_empty() {
+            errors.push(format!(
+                "Missing automation scripts: {}. Reinstall the app or copy them to {}",
+                missing.join(", "),
+                self.overrides.display()
+            ));
+        }
+
+        let node_version = which("node").ok().and_then(|node| {
+            let out = Command::new(node).arg("--version").stdin(Stdio::null()).output().ok()?;
+            out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        });
+        let has_playwright = node_version.is_some() && {
+            let mut probe = Command::new("node");
+            probe
+                .args(["-e", "require.resolve('playwright')"])
+                .env("NODE_PATH", self.node_path())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            // Resolve the way a bundled script would, from its own directory upwards
+            if self.bundled.is_dir() {
+                probe.current_dir(&self.bundled);
+            }
+            probe.status().map(|s| s.success()).unwrap_or(false)
+        };
+        if node_version.is_none() {
+            errors.push("Node.js is not installed or not on PATH; automation scripts need it".to_string());
+        } else if !has_playwright {
+            errors.push(format!(
+                "Playwright is not installed; run `npm install playwright && npx playwright install chromium` in {}",
+                self.overrides.display()
+            ));
+        }
+
+        ScriptHealth {
+            bundled_dir: self.bundled.display().to_string(),
+            override_dir: self.overrides.display().to_string(),
+            node_version,
+            has_playwright,
+            missing,
+            errors,
+        }
+    }
+}
+
+// Startup integrity check; problems go to the activity log.
+pub fn check_on_startup(scripts: &Scripts, state: &DbState) {
+    let health = scripts.check();
+    let conn = state.0.lock().unwrap();
+    for error in &health.errors {
+        let _ = log_event(&conn, None, error, "Error");
+    }
+}
+
+#[tauri::command]
+pub async fn check_scripts(scripts: State<'_, Scripts>) -> Result<ScriptHealth, String> {
+    let scripts = scripts.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || scripts.check())
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,466 @@
+use crate::db::{DbState, log_event};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+// Encrypted with the vault key to tell a wrong passphrase from a right one
+const CHECK_PLAINTEXT: &[u8] = b"personaliz-vault";
+const LOCKED: &str = "The secrets vault is locked; unlock it with your passphrase first";
+
+// Secrets (API keys, tokens) are stored AES-256-GCM encrypted in the `secrets`
+// table. The key comes either from a random keyfile in the app data dir (the
+// default, unlocked at startup) or from a passphrase run through Argon2, in
+// which case the vault stays locked until `unlock_vault`. Plaintext never
+// goes back to the webview.
+#[derive(Clone)]
+pub struct Vault {
+    key: Arc<Mutex<Option<[u8; 32]>>>,
+    keyfile: PathBuf,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SecretInfo {
+    pub provider: String,
+    pub name: String,
+    // e.g. "••••3xYz"; None while the vault is locked
+    pub masked: Option<String>,
+    pub created_at: String,
+    pub rotated_at: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VaultStatus {
+    pub mode: String, // keyfile, passphrase
+    pub unlocked: bool,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+fn setting(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?", [key], |row| row.get(0)).optional()
+}
+
+fn put_setting(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)", [key, value])?;
+    Ok(())
+}
+
+fn mode(conn: &Connection) -> rusqlite::Result<String> {
+    Ok(setting(conn, "vault_mode")?.unwrap_or_else(|| "keyfile".into()))
+}
+
+fn encrypt(key: &[u8; 32], aad: &str, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: aad.as_bytes() })
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+fn decrypt(key: &[u8; 32], aad: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if nonce.len() != 12 {
+        return Err("Corrupt secret".into());
+    }
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: aad.as_bytes() })
+        .map_err(|_| "Secret could not be decrypted with the current vault key".to_string())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn random_key() -> [u8; 32] {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+// Writes the key to a file next to `path` that only the user can read.
+// Renaming it over `path` then replaces the keyfile in one step.
+fn stage_keyfile(path: &Path, key: &[u8; 32]) -> Result<PathBuf, String> {
+    let staged = path.with_extension("key.new");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&staged)
+        .and_then(|mut file| file.write_all(to_hex(key).as_bytes()).and_then(|_| file.sync_all()))
+        .map_err(|e| format!("Failed to write vault keyfile: {}", e))?;
+    Ok(staged)
+}
+
+fn write_keyfile(path: &Path, key: &[u8; 32]) -> Result<(), String> {
+    let staged = stage_keyfile(path, key)?;
+    std::fs::rename(&staged, path).map_err(|e| format!("Failed to write vault keyfile: {}", e))
+}
+
+// id, provider, name, nonce, ciphertext
+type SealedRow = (i64, String, String, Vec<u8>, Vec<u8>);
+
+fn aad(provider: &str, name: &str) -> String {
+    format!("{}/{}", provider, name)
+}
+
+impl Vault {
+    // A missing or unreadable keyfile leaves the vault locked instead of failing
+    // startup; the problem is logged and `unlock_vault` tries the keyfile again.
+    pub fn open(conn: &Connection, keyfile: PathBuf) -> Result<Vault, String> {
+        let vault = Vault { key: Arc::new(Mutex::new(None)), keyfile };
+        if mode(conn).map_err(|e| e.to_string())? == "keyfile" {
+            match vault.load_keyfile(conn) {
+                Ok(key) => {
+                    *vault.key.lock().unwrap() = Some(key);
+                    vault.adopt_plaintext(conn)?;
+                }
+                Err(e) => {
+                    let _ = log_event(conn, None, &format!("Secrets vault is locked: {}", e), "Error");
+                }
+            }
+        }
+        Ok(vault)
+    }
+
+    fn load_keyfile(&self, conn: &Connection) -> Result<[u8; 32], String> {
+        match std::fs::read_to_string(&self.keyfile) {
+            Ok(raw) => from_hex(raw.trim())
+                .and_then(|k| <[u8; 32]>::try_from(k).ok())
+                .ok_or_else(|| format!("Vault keyfile {} is corrupt", self.keyfile.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // A fresh key could never decrypt what is already stored
+                let sealed: bool = conn
+                    .query_row("SELECT EXISTS (SELECT 1 FROM secrets)", [], |row| row.get(0))
+                    .map_err(|e| e.to_string())?;
+                if sealed {
+                    return Err(format!(
+                        "Vault keyfile {} is missing but encrypted secrets exist; restore the keyfile or delete the secrets",
+                        self.keyfile.display()
+                    ));
+                }
+                let key = random_key();
+                write_keyfile(&self.keyfile, &key)?;
+                Ok(key)
+            }
+            Err(e) => Err(format!("Cannot read vault keyfile {}: {}", self.keyfile.display(), e)),
+        }
+    }
+
+    fn current_key(&self) -> Result<[u8; 32], String> {
+        (*self.key.lock().unwrap()).ok_or_else(|| LOCKED.to_string())
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.key.lock().unwrap().is_some()
+    }
+
+    // Older versions kept the LLM key in plain text in `settings`.
+    fn adopt_plaintext(&self, conn: &Connection) -> Result<(), String> {
+        if let Some(key) = setting(conn, "llm_api_key").map_err(|e| e.to_string())? {
+            if !key.trim().is_empty() {
+                self.put(conn, "openai", "default", key.trim())?;
+                let _ = log_event(conn, None, "Moved the LLM API key into the encrypted secrets vault", "Info");
+            }
+            conn.execute("DELETE FROM settings WHERE key = 'llm_api_key'", []).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn unlock(&self, conn: &Connection, passphrase: &str) -> Result<(), String> {
+        let salt = setting(conn, "vault_salt").map_err(|e| e.to_string())?.and_then(|s| from_hex(&s));
+        let check = setting(conn, "vault_check").map_err(|e| e.to_string())?.and_then(|s| from_hex(&s));
+        let (Some(salt), Some(check)) = (salt, check) else {
+            // Keyfile mode: pick up a keyfile restored since startup
+            let key = self.load_keyfile(conn)?;
+            *self.key.lock().unwrap() = Some(key);
+            return self.adopt_plaintext(conn);
+        };
+        let key = derive_key(passphrase, &salt)?;
+        let (nonce, ciphertext) = check.split_at(12.min(check.len()));
+        if decrypt(&key, "vault", nonce, ciphertext).ok().as_deref() != Some(CHECK_PLAINTEXT) {
+            return Err("Wrong passphrase".into());
+        }
+        *self.key.lock().unwrap() = Some(key);
+        self.adopt_plaintext(conn)
+    }
+
+    pub fn lock(&self) {
+        *self.key.lock().unwrap() = None;
+    }
+
+    pub fn put(&self, conn: &Connection, provider: &str, name: &str, value: &str) -> Result<(), String> {
+        let key = self.current_key()?;
+        let (nonce, ciphertext) = encrypt(&key, &aad(provider, name), value.as_bytes())?;
+        conn.execute(
+            "INSERT INTO secrets (provider, name, nonce, ciphertext, created_at) VALUES (?, ?, ?, ?, CURRENT_TIMESTAMP)
+             ON CONFLICT (provider, name) DO UPDATE SET
+                nonce = excluded.nonce, ciphertext = excluded.ciphertext, rotated_at = CURRENT_TIMESTAMP",
+            params![provider, name, nonce, ciphertext],
+        ).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn get(&self, conn: &Connection, provider: &str, name: &str) -> Result<Option<String>, String> {
+        let row: Option<(Vec<u8>, Vec<u8>)> = conn
+            .query_row(
+                "SELECT nonce, ciphertext FROM secrets WHERE provider = ? AND name = ?",
+                [provider, name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some((nonce, ciphertext)) = row else {
+            return Ok(None);
+        };
+        let plaintext = decrypt(&self.current_key()?, &aad(provider, name), &nonce, &ciphertext)?;
+        String::from_utf8(plaintext).map(Some).map_err(|e| e.to_string())
+    }
+
+    // Re-encrypts every secret under `new_key` in one transaction.
+    fn rekey(&self, conn: &mut Connection, new_key: [u8; 32], settings: &[(&str, Option<String>)]) -> Result<(), String> {
+        let old_key = self.current_key()?;
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let rows: Vec<SealedRow> = {
+            let mut stmt = tx.prepare("SELECT id, provider, name, nonce, ciphertext FROM secrets").map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())?
+        };
+        for (id, provider, name, nonce, ciphertext) in rows {
+            let aad = aad(&provider, &name);
+            let plaintext = decrypt(&old_key, &aad, &nonce, &ciphertext)?;
+            let (nonce, ciphertext) = encrypt(&new_key, &aad, &plaintext)?;
+            tx.execute("UPDATE secrets SET nonce = ?, ciphertext = ? WHERE id = ?", params![nonce, ciphertext, id])
+                .map_err(|e| e.to_string())?;
+        }
+        for (key, value) in settings {
+            match value {
+                Some(value) => put_setting(&tx, key, value).map_err(|e| e.to_string())?,
+                None => {
+                    tx.execute("DELETE FROM settings WHERE key = ?", [key]).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        *self.key.lock().unwrap() = Some(new_key);
+        Ok(())
+    }
+
+    // Switches to (or changes) passphrase protection, or back to the keyfile
+    // with `None`. Either way every secret is re-encrypted under a fresh key.
+    pub fn set_passphrase(&self, conn: &mut Connection, passphrase: Option<&str>) -> Result<(), String> {
+        match passphrase {
+            Some(passphrase) => {
+                if passphrase.chars().count() < 8 {
+                    return Err("Passphrase must be at least 8 characters".into());
+                }
+                let mut salt = [0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                let key = derive_key(passphrase, &salt)?;
+                let (nonce, ciphertext) = encrypt(&key, "vault", CHECK_PLAINTEXT)?;
+                let check = [nonce, ciphertext].concat();
+                self.rekey(conn, key, &[
+                    ("vault_mode", Some("passphrase".into())),
+                    ("vault_salt", Some(to_hex(&salt))),
+                    ("vault_check", Some(to_hex(&check))),
+                ])?;
+                // The derived key must not sit next to the data any more
+                let _ = std::fs::remove_file(&self.keyfile);
+            }
+            None => {
+                // The old keyfile stays in place until the secrets are re-encrypted
+                let key = random_key();
+                let staged = stage_keyfile(&self.keyfile, &key)?;
+                if let Err(e) = self.rekey(conn, key, &[("vault_mode", None), ("vault_salt", None), ("vault_check", None)]) {
+                    let _ = std::fs::remove_file(&staged);
+                    return Err(e);
+                }
+                std::fs::rename(&staged, &self.keyfile).map_err(|e| {
+                    format!("Secrets were re-encrypted but the new keyfile could not be moved into place; it is at {}: {}", staged.display(), e)
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn mask(secret: &str) -> String {
+    let tail: String = secret.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+    if secret.chars().count() <= 8 {
+        "••••".to_string()
+    } else {
+        format!("••••{}", tail)
+    }
+}
+
+pub fn exists(conn: &Connection, provider: &str, name: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM secrets WHERE provider = ? AND name = ?)",
+        [provider, name],
+        |row| row.get(0),
+    )
+}
+
+#[tauri::command]
+pub fn get_vault_status(state: State<DbState>, vault: State<Vault>) -> Result<VaultStatus, String> {
+    let conn = state.0.lock().unwrap();
+    Ok(VaultStatus { mode: mode(&conn).map_err(|e| e.to_string())?, unlocked: vault.is_unlocked() })
+}
+
+#[tauri::command]
+pub fn unlock_vault(state: State<DbState>, vault: State<Vault>, passphrase: String) -> Result<(), String> {
+    let conn = state.0.lock().unwrap();
+    vault.unlock(&conn, &passphrase)
+}
+
+#[tauri::command]
+pub fn lock_vault(state: State<DbState>, vault: State<Vault>) -> Result<(), String> {
+    let conn = state.0.lock().unwrap();
+    if mode(&conn).map_err(|e| e.to_string())? != "passphrase" {
+        return Err("Set a passphrase before locking the vault".into());
+    }
+    vault.lock();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_vault_passphrase(state: State<DbState>, vault: State<Vault>, passphrase: Option<String>) -> Result<(), String> {
+    let mut conn = state.0.lock().unwrap();
+    vault.set_passphrase(&mut conn, passphrase.as_deref())?;
+    let message = if passphrase.is_some() { "Secrets vault is now protected by a passphrase" } else { "Secrets vault now uses the local keyfile" };
+    let _ = log_event(&conn, None, message, "Info");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_secrets(state: State<DbState>, vault: State<Vault>) -> Result<Vec<SecretInfo>, String> {
+    let conn = state.0.lock().unwrap();
+    let mut stmt = conn
+        .prepare("SELECT provider, name, created_at, rotated_at FROM secrets ORDER BY provider, name")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SecretInfo { provider: row.get(0)?, name: row.get(1)?, masked: None, created_at: row.get(2)?, rotated_at: row.get(3)? })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut secrets = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())?;
+    if vault.is_unlocked() {
+        for secret in &mut secrets {
+            secret.masked = vault.get(&conn, &secret.provider, &secret.name).ok().flatten().map(|s| mask(&s));
+        }
+    }
+    Ok(secrets)
+}
+
+// Creates the secret or rotates it to a new value.
+#[tauri::command]
+pub fn set_secret(state: State<DbState>, vault: State<Vault>, provider: String, name: String, value: String) -> Result<(), String> {
+    if provider.trim().is_empty() || name.trim().is_empty() {
+        return Err("Secrets need a provider and a name".into());
+    }
+    if value.trim().is_empty() {
+        return Err("Secret value cannot be empty".into());
+    }
+    let conn = state.0.lock().unwrap();
+    vault.put(&conn, provider.trim(), name.trim(), value.trim())?;
+    let _ = log_event(&conn, None, &format!("Stored secret {}/{}", provider.trim(), name.trim()), "Info");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_secret(state: State<DbState>, provider: String, name: String) -> Result<(), String> {
+    let conn = state.0.lock().unwrap();
+    let deleted = conn
+        .execute("DELETE FROM secrets WHERE provider = ? AND name = ?", [&provider, &name])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Secret {}/{} not found", provider, name));
+    }
+    let _ = log_event(&conn, None, &format!("Deleted secret {}/{}", provider, name), "Info");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str) -> (Connection, PathBuf) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run(&mut conn, None).unwrap();
+        let dir = std::env::temp_dir().join(format!("personaliz-vault-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        (conn, dir.join("vault.key"))
+    }
+
+    #[test]
+    fn a_missing_keyfile_is_only_replaced_while_there_is_nothing_to_decrypt() {
+        let (conn, keyfile) = setup("missing");
+        let vault = Vault::open(&conn, keyfile.clone()).unwrap();
+        vault.put(&conn, "openai", "default", "sk-test").unwrap();
+
+        let saved = std::fs::read(&keyfile).unwrap();
+        std::fs::remove_file(&keyfile).unwrap();
+        let locked = Vault::open(&conn, keyfile.clone()).unwrap();
+        assert!(!locked.is_unlocked());
+        assert!(!keyfile.exists());
+        let logged: String = conn.query_row("SELECT message FROM logs ORDER BY id DESC LIMIT 1", [], |row| row.get(0)).unwrap();
+        assert!(logged.contains("missing"), "{}", logged);
+
+        std::fs::write(&keyfile, saved).unwrap();
+        locked.unlock(&conn, "").unwrap();
+        assert_eq!(locked.get(&conn, "openai", "default").unwrap().as_deref(), Some("sk-test"));
+        std::fs::remove_dir_all(keyfile.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn a_corrupt_keyfile_leaves_the_vault_locked() {
+        let (conn, keyfile) = setup("corrupt");
+        std::fs::write(&keyfile, "not hex").unwrap();
+        let vault = Vault::open(&conn, keyfile.clone()).unwrap();
+        assert!(!vault.is_unlocked());
+        assert_eq!(std::fs::read_to_string(&keyfile).unwrap(), "not hex");
+        std::fs::remove_dir_all(keyfile.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn going_back_to_the_keyfile_keeps_secrets_readable() {
+        let (mut conn, keyfile) = setup("rekey");
+        let vault = Vault::open(&conn, keyfile.clone()).unwrap();
+        vault.put(&conn, "openai", "default", "sk-test").unwrap();
+        vault.set_passphrase(&mut conn, Some("correct horse")).unwrap();
+        assert!(!keyfile.exists());
+
+        vault.set_passphrase(&mut conn, None).unwrap();
+        assert!(!keyfile.with_extension("key.new").exists());
+        let reopened = Vault::open(&conn, keyfile.clone()).unwrap();
+        assert_eq!(reopened.get(&conn, "openai", "default").unwrap().as_deref(), Some("sk-test"));
+        std::fs::remove_dir_all(keyfile.parent().unwrap()).unwrap();
+    }
+}
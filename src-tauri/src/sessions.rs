@@ -0,0 +1,214 @@
+use crate::db::{DbState, log_event};
+use crate::openclaw::{self, ScriptEvent, OpenClawResult};
+use crate::scripts::Scripts;
+use crate::secrets::Vault;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+
+// Vault provider the encrypted storage states are kept under, named <site>/<account>
+const PROVIDER: &str = "session";
+const LOGIN_SCRIPT: &str = "login.cjs";
+// Time the user gets to finish logging in, including any 2FA
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+// Error code scripts report when the stored session no longer logs them in
+pub const EXPIRED_CODE: &str = "session_expired";
+pub const STATE_ENV: &str = "PERSONALIZ_STORAGE_STATE";
+
+// Playwright storage states (cookies and local storage) for the accounts
+// browser automations act as. They are created by an interactive login run,
+// kept encrypted in the vault, and only written out to disk (0600, in the app
+// data dir) for the duration of a script run.
+#[derive(Clone, Debug)]
+pub struct Sessions {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionInfo {
+    pub site: String,
+    pub account: String,
+    // None until the first successful login
+    pub saved_at: Option<String>,
+    // Agents flagged "needs login" for this session
+    pub agents_needing_login: Vec<i32>,
+}
+
+// A decrypted storage state on disk; removed again on drop.
+pub struct SessionFile {
+    pub path: PathBuf,
+    original: String,
+}
+
+impl Drop for SessionFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn valid_part(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '@')
+}
+
+pub fn key(site: &str, account: &str) -> Result<String, String> {
+    if !valid_part(site) || !valid_part(account) {
+        return Err(format!("Invalid session '{}/{}'", site, account));
+    }
+    Ok(format!("{}/{}", site, account))
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    use std::io::Write;
+    options.open(path).and_then(|mut f| f.write_all(contents.as_bytes())).map_err(|e| e.to_string())
+}
+
+impl Sessions {
+    pub fn new(dir: PathBuf) -> Self {
+        // Files left behind by a crash hold decrypted cookies
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.filter_map(|e| e.ok()) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+        Sessions { dir }
+    }
+
+    fn scratch(&self) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        Ok(self.dir.join(format!("{}.json", uuid::Uuid::new_v4())))
+    }
+
+    // Writes the stored state for `key` to a private file, or None if there is none yet.
+    pub fn checkout(&self, conn: &Connection, vault: &Vault, key: &str) -> Result<Option<SessionFile>, String> {
+        let Some(state) = vault.get(conn, PROVIDER, key)? else {
+            return Ok(None);
+        };
+        let path = self.scratch()?;
+        write_private(&path, &state)?;
+        Ok(Some(SessionFile { path, original: state }))
+    }
+
+    // Stores the state back if the script refreshed it.
+    pub fn checkin(&self, conn: &Connection, vault: &Vault, key: &str, file: &SessionFile) -> Result<(), String> {
+        match std::fs::read_to_string(&file.path) {
+            Ok(state) if state != file.original && serde_json::from_str::<serde_json::Value>(&state).is_ok() => {
+                vault.put(conn, PROVIDER, key, &state)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Whether a script gave up because the session it was handed is no longer logged in.
+pub fn expired(result: &OpenClawResult) -> bool {
+    matches!(result.script_error(), Some(ScriptEvent::Error { code: Some(code), .. }) if code == EXPIRED_CODE)
+}
+
+pub fn flag_needs_login(conn: &Connection, agent_id: i32, key: &str) -> rusqlite::Result<()> {
+    conn.execute("UPDATE agents SET needs_login = ? WHERE id = ?", rusqlite::params![key, agent_id])?;
+    log_event(conn, Some(agent_id), &format!("Agent needs a fresh login for {}", key), "Error")?;
+    Ok(())
+}
+
+pub fn clear_needs_login(conn: &Connection, agent_id: i32) -> rusqlite::Result<()> {
+    conn.execute("UPDATE agents SET needs_login = NULL WHERE id = ? AND needs_login IS NOT NULL", [agent_id])?;
+    Ok(())
+}
+
+// Opens a visible browser on the site's login page and saves the session once
+// the user is logged in. Agents waiting on this session are unflagged.
+#[tauri::command]
+pub async fn login_session(
+    state: State<'_, DbState>,
+    vault: State<'_, Vault>,
+    scripts: State<'_, Scripts>,
+    sessions: State<'_, Sessions>,
+    site: String,
+    account: Option<String>,
+) -> Result<(), String> {
+    let key = key(&site, account.as_deref().unwrap_or("default"))?;
+    let script = scripts.resolve(LOGIN_SCRIPT)?;
+    let file = {
+        let conn = state.0.lock().unwrap();
+        sessions.checkout(&conn, &vault, &key)?
+    };
+    // A first login starts from an empty file the script fills in
+    let file = match file {
+        Some(file) => file,
+        None => {
+            let path = sessions.scratch()?;
+            write_private(&path, "")?;
+            SessionFile { path, original: String::new() }
+        }
+    };
+
+    let env = [(STATE_ENV.to_string(), file.path.display().to_string())];
+    let (_tx, cancel) = tokio::sync::watch::channel(false);
+    let result = openclaw::run_script_async(&script, &scripts.node_path(), &env, &site, LOGIN_TIMEOUT, cancel, None).await;
+
+    let conn = state.0.lock().unwrap();
+    if !result.success {
+        let error = format!("Login to {} failed: {}", site, result.error.unwrap_or_else(|| "script failed".to_string()));
+        let _ = log_event(&conn, None, &error, "Error");
+        return Err(error);
+    }
+    sessions.checkin(&conn, &vault, &key, &file)?;
+    conn.execute("UPDATE agents SET needs_login = NULL WHERE needs_login = ?", [&key]).map_err(|e| e.to_string())?;
+    let _ = log_event(&conn, None, &format!("Saved browser session {}", key), "Info");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_sessions(state: State<DbState>) -> Result<Vec<SessionInfo>, String> {
+    let conn = state.0.lock().unwrap();
+    // Saved sessions, plus ones agents are waiting on that were never saved
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, COALESCE(rotated_at, created_at) FROM secrets WHERE provider = ?1
+             UNION
+             SELECT DISTINCT needs_login, NULL FROM agents
+             WHERE needs_login IS NOT NULL AND needs_login NOT IN (SELECT name FROM secrets WHERE provider = ?1)
+             ORDER BY 1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([PROVIDER], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    let mut waiting = conn.prepare("SELECT id FROM agents WHERE needs_login = ? ORDER BY id").map_err(|e| e.to_string())?;
+    rows.into_iter()
+        .map(|(name, saved_at)| {
+            let agents_needing_login = waiting
+                .query_map([&name], |row| row.get(0))
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<i32>>>()
+                .map_err(|e| e.to_string())?;
+            let (site, account) = name.split_once('/').unwrap_or((name.as_str(), "default"));
+            Ok(SessionInfo { site: site.to_string(), account: account.to_string(), saved_at, agents_needing_login })
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn delete_session(state: State<DbState>, site: String, account: Option<String>) -> Result<(), String> {
+    let key = key(&site, account.as_deref().unwrap_or("default"))?;
+    let conn = state.0.lock().unwrap();
+    let deleted = conn
+        .execute("DELETE FROM secrets WHERE provider = ? AND name = ?", [PROVIDER, key.as_str()])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Session {} not found", key));
+    }
+    let _ = log_event(&conn, None, &format!("Deleted browser session {}", key), "Info");
+    Ok(())
+}
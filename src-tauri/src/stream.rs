@@ -0,0 +1,156 @@
+use crate::openclaw::{LineSink, Pipe, ScriptEvent};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+// Lines kept per run for late subscribers
+const BACKLOG: usize = 500;
+// Lines waiting to be forwarded before new ones are dropped; scripts never wait on the UI
+const QUEUE: usize = 256;
+
+// Announces every run on this event so the UI can subscribe to its channel
+pub const RUN_STARTED_EVENT: &str = "run-started";
+
+pub fn channel(run_id: i64) -> String {
+    format!("run-output:{}", run_id)
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunOutput {
+    Stdout { line: String },
+    Stderr { line: String },
+    // A protocol event parsed from stdout
+    Event { event: ScriptEvent },
+    // Lines dropped because output arrived faster than it could be forwarded
+    Lagged { dropped: u64 },
+    Finished { status: String },
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RunEvent {
+    pub run_id: i64,
+    // Increases by one per event, so subscribers can merge the backlog with live events
+    pub seq: u64,
+    #[serde(flatten)]
+    pub output: RunOutput,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RunStarted {
+    pub run_id: i64,
+    pub agent_id: i32,
+    pub trigger: String,
+    pub channel: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RunSubscription {
+    pub channel: String,
+    pub backlog: Vec<RunEvent>,
+}
+
+#[derive(Default)]
+struct Backlog {
+    events: VecDeque<RunEvent>,
+    next_seq: u64,
+    status: Option<String>,
+}
+
+// Forwards live run output to the webview on a per-run channel and keeps the
+// recent lines of runs still going for `subscribe_run`.
+#[derive(Clone, Default)]
+pub struct Streams {
+    app: Option<AppHandle>,
+    runs: Arc<Mutex<HashMap<i64, Backlog>>>,
+}
+
+impl Streams {
+    pub fn new(app: Option<AppHandle>) -> Self {
+        Streams { app, runs: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    fn push(&self, run_id: i64, output: RunOutput) {
+        let event = {
+            let mut runs = self.runs.lock().unwrap();
+            let Some(backlog) = runs.get_mut(&run_id) else {
+                return;
+            };
+            let event = RunEvent { run_id, seq: backlog.next_seq, output };
+            backlog.next_seq += 1;
+            backlog.events.push_back(event.clone());
+            if backlog.events.len() > BACKLOG {
+                backlog.events.pop_front();
+            }
+            event
+        };
+        if let Some(app) = &self.app {
+            let _ = app.emit(&channel(run_id), event);
+        }
+    }
+
+    // Starts streaming a run. The returned sink feeds the channel until it and
+    // all its clones are dropped; then `Finished` goes out with the status
+    // recorded by `finish`.
+    pub fn open(&self, run_id: i64, agent_id: i32, trigger: &str) -> LineSink {
+        self.runs.lock().unwrap().insert(run_id, Backlog::default());
+        if let Some(app) = &self.app {
+            let started = RunStarted { run_id, agent_id, trigger: trigger.to_string(), channel: channel(run_id) };
+            let _ = app.emit(RUN_STARTED_EVENT, started);
+        }
+
+        let (tx, mut rx) = mpsc::channel::<(Pipe, String)>(QUEUE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let streams = self.clone();
+        let lagged = dropped.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some((pipe, line)) = rx.recv().await {
+                let missed = lagged.swap(0, Ordering::Relaxed);
+                if missed > 0 {
+                    streams.push(run_id, RunOutput::Lagged { dropped: missed });
+                }
+                let output = match pipe {
+                    Pipe::Stderr => RunOutput::Stderr { line },
+                    Pipe::Stdout => match ScriptEvent::parse(&line) {
+                        Ok(event) => RunOutput::Event { event },
+                        Err(_) => RunOutput::Stdout { line },
+                    },
+                };
+                streams.push(run_id, output);
+            }
+            let status = streams.runs.lock().unwrap().get_mut(&run_id).and_then(|b| b.status.take());
+            streams.push(run_id, RunOutput::Finished { status: status.unwrap_or_else(|| "Finished".into()) });
+            streams.runs.lock().unwrap().remove(&run_id);
+        });
+
+        Arc::new(move |pipe, line| {
+            if tx.try_send((pipe, line.to_string())).is_err() {
+                dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        })
+    }
+
+    // Events not tied to a single run, e.g. approval reminders.
+    pub fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app) = &self.app {
+            let _ = app.emit(event, payload);
+        }
+    }
+
+    pub fn finish(&self, run_id: i64, status: &str) {
+        if let Some(backlog) = self.runs.lock().unwrap().get_mut(&run_id) {
+            backlog.status = Some(status.to_string());
+        }
+    }
+
+    pub fn subscribe(&self, run_id: i64) -> Option<RunSubscription> {
+        let runs = self.runs.lock().unwrap();
+        runs.get(&run_id).map(|backlog| RunSubscription {
+            channel: channel(run_id),
+            backlog: backlog.events.iter().cloned().collect(),
+        })
+    }
+}
@@ -0,0 +1,686 @@
+use crate::db::{Agent, DbState, log_event};
+use crate::openclaw::{self, Capture, LineSink, OpenClawClient, OpenClawResult, Trend};
+use crate::plugins::{self, Plugin};
+use crate::scripts::Scripts;
+use crate::secrets::Vault;
+use crate::sessions::{self, Sessions};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+use tokio::sync::watch;
+
+// What an agent does is stored in `agents.openclaw_task` as a JSON spec, e.g.
+// {"kind":"hashtag_comment","params":{"hashtag":"#openclaw"}}. Adding a new agent
+// type means implementing `TaskKind` and listing it in `KINDS`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TaskSpec {
+    pub kind: String,
+    #[serde(default)]
+    pub params: serde_json::Map<String, serde_json::Value>,
+    // Overrides the kind's default script timeout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TaskInput {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+    pub default: Option<&'static str>,
+}
+
+pub enum TaskOutput {
+    // The work is finished; carries a short summary
+    Done(String),
+    // Content to publish, held for approval when the kind requires it
+    Draft(String),
+}
+
+pub struct TaskContext<'a> {
+    pub state: &'a DbState,
+    pub vault: &'a Vault,
+    pub scripts: &'a Scripts,
+    pub sessions: &'a Sessions,
+    pub agent: &'a Agent,
+    pub spec: &'a TaskSpec,
+    // Scripts run against local mock targets; nothing is published
+    pub dry_run: bool,
+    pub run_id: i64,
+    // Set while posting an approved draft
+    pub publication: Option<Publication>,
+    pub timeout: Duration,
+    pub cancel: watch::Receiver<bool>,
+    // Live stdout/stderr for anyone watching the run
+    pub output: LineSink,
+}
+
+// Identifies the approval being posted. The key is the same on every attempt,
+// and scripts add it to the ledger once the content is out, so a retry can
+// tell a re-sent post from a new one.
+pub struct Publication {
+    pub key: String,
+    pub ledger: PathBuf,
+}
+
+impl TaskContext<'_> {
+    pub fn log(&self, message: &str, level: &str) {
+        let conn = self.state.0.lock().unwrap();
+        let _ = log_event(&conn, Some(self.agent.id), message, level);
+    }
+
+    // Routed through the configured LLM providers, like the chat, and bounded
+    // by the run's timeout and cancel like a script.
+    pub fn chat(&self, prompt: &str) -> Result<String, String> {
+        let messages = [crate::llm::ChatMessage::user(prompt)];
+        crate::llm::complete_blocking(self.state, self.vault, &messages, self.timeout, self.cancel.clone()).map(|c| c.content)
+    }
+
+    fn record(&self, result: &OpenClawResult) {
+        let conn = self.state.0.lock().unwrap();
+        let _ = crate::runs::record_script(&conn, self.run_id, result);
+        if self.dry_run {
+            let captures: Vec<Capture> = result.captures().cloned().collect();
+            let _ = crate::runs::record_captures(&conn, self.run_id, &captures);
+        }
+    }
+
+    // Checks text a step is about to send against the content policy. There is
+    // no draft to hold for approval, so any violation fails the step. The text
+    // need not be a whole post, so a missing required hashtag does not count.
+    pub fn screen(&self, platform: &str, text: &str) -> Result<(), String> {
+        let mut violations = {
+            let conn = self.state.0.lock().unwrap();
+            crate::policy::ContentPolicy::load(&conn)?.evaluate(&conn, platform, text)?
+        };
+        violations.retain(|v| v.rule != crate::policy::Rule::RequiredHashtag);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let reason = crate::policy::describe(&violations);
+        if self.dry_run {
+            self.capture("policy_block", Some(platform), &reason);
+        }
+        Err(format!("Blocked by the content policy: {}", reason))
+    }
+
+    // Records what a dry run would have done, for steps that cannot run against a mock.
+    pub fn capture(&self, action: &str, target: Option<&str>, content: &str) {
+        let capture = Capture { action: action.to_string(), target: target.map(str::to_string), content: content.to_string() };
+        let conn = self.state.0.lock().unwrap();
+        let _ = crate::runs::record_captures(&conn, self.run_id, &[capture]);
+        let _ = log_event(&conn, Some(self.agent.id), &format!("Dry run - would {}: {}", action, content), "Info");
+    }
+
+    // Kinds run plugins and OpenClaw CLI calls through here so their output
+    // lands in the run record. The plugin's bound parameters are its JSON
+    // argument and its required secrets go in the environment. Plugins with a
+    // `session` run logged in as `account` on that site.
+    pub fn run_plugin(&self, plugin: &Plugin, args: &serde_json::Map<String, serde_json::Value>, account: &str) -> OpenClawResult {
+        let arg = serde_json::Value::Object(args.clone()).to_string();
+        let result = match (self.plugin_secrets(plugin), plugin.manifest.session.as_deref()) {
+            (Err(e), _) => OpenClawResult::failed(e),
+            // Dry runs drive the mock pages, which need no login
+            (Ok(env), Some(site)) if !self.dry_run => self.logged_in(&plugin.entrypoint(), env, &arg, site, account),
+            (Ok(env), _) => self.node(&plugin.entrypoint(), &env, &arg),
+        };
+        self.record(&result);
+        result
+    }
+
+    // Runs `script` with the stored session for `account` on `site`. Without a
+    // usable session the agent is flagged "needs login" rather than left
+    // waiting for someone to log in by hand.
+    fn logged_in(&self, script: &Path, mut env: Vec<(String, String)>, arg: &str, site: &str, account: &str) -> OpenClawResult {
+        let key = match sessions::key(site, account) {
+            Ok(key) => key,
+            Err(e) => return OpenClawResult::failed(e),
+        };
+        if self.agent.needs_login.as_deref() == Some(key.as_str()) {
+            return OpenClawResult::failed(format!("Log in to {} again before this agent can run", key));
+        }
+        let checkout = {
+            let conn = self.state.0.lock().unwrap();
+            self.sessions.checkout(&conn, self.vault, &key)
+        };
+        let file = match checkout {
+            Ok(Some(file)) => file,
+            Ok(None) => {
+                let conn = self.state.0.lock().unwrap();
+                let _ = sessions::flag_needs_login(&conn, self.agent.id, &key);
+                return OpenClawResult::failed(format!("No saved login for {}; log in from Settings first", key));
+            }
+            Err(e) => return OpenClawResult::failed(e),
+        };
+
+        env.push((sessions::STATE_ENV.to_string(), file.path.display().to_string()));
+        let result = self.node(script, &env, arg);
+
+        let conn = self.state.0.lock().unwrap();
+        if sessions::expired(&result) {
+            let _ = sessions::flag_needs_login(&conn, self.agent.id, &key);
+        } else if result.success {
+            if let Err(e) = self.sessions.checkin(&conn, self.vault, &key, &file) {
+                let _ = log_event(&conn, Some(self.agent.id), &format!("Could not save the refreshed session for {}: {}", key, e), "Error");
+            }
+            let _ = sessions::clear_needs_login(&conn, self.agent.id);
+        }
+        result
+    }
+
+    fn plugin_secrets(&self, plugin: &Plugin) -> Result<Vec<(String, String)>, String> {
+        let conn = self.state.0.lock().unwrap();
+        plugin
+            .manifest
+            .secrets
+            .iter()
+            .map(|secret| match self.vault.get(&conn, &secret.provider, &secret.name)? {
+                Some(value) => Ok((plugins::secret_env(secret), value)),
+                None => Err(format!(
+                    "Plugin '{}' needs the secret {}/{}; add it in Settings",
+                    plugin.manifest.name, secret.provider, secret.name
+                )),
+            })
+            .collect()
+    }
+
+    fn node(&self, script: &Path, env: &[(String, String)], arg: &str) -> OpenClawResult {
+        let node_path = self.scripts.node_path();
+        let mut env = env.to_vec();
+        if self.dry_run {
+            env.push(("PERSONALIZ_DRY_RUN".into(), "1".into()));
+            env.push(("PERSONALIZ_MOCK_DIR".into(), self.scripts.mock_dir().display().to_string()));
+        }
+        if let Some(publication) = &self.publication {
+            env.push(("PERSONALIZ_IDEMPOTENCY_KEY".into(), publication.key.clone()));
+            env.push(("PERSONALIZ_PUBLISH_LEDGER".into(), publication.ledger.display().to_string()));
+        }
+        openclaw::run_script(script, &node_path, &env, arg, self.timeout, self.cancel.clone(), Some(self.output.clone()))
+    }
+
+    fn openclaw(&self) -> Result<OpenClawClient, String> {
+        let client = OpenClawClient::locate().map_err(|e| e.to_string())?;
+        Ok(OpenClawClient { output: Some(self.output.clone()), ..client })
+    }
+
+    pub fn search_trends(&self, query: &str, limit: u32) -> Result<Vec<Trend>, String> {
+        let client = self.openclaw()?;
+        let (raw, trends) = tauri::async_runtime::block_on(client.search_trends(query, limit, self.timeout, self.cancel.clone()));
+        self.record(&raw);
+        trends.map_err(|e| e.to_string())
+    }
+
+    pub fn run_openclaw_task(&self, task: &str, input: &serde_json::Value) -> Result<serde_json::Value, String> {
+        let client = self.openclaw()?;
+        let (raw, output) = tauri::async_runtime::block_on(client.run_task(task, input, self.timeout, self.cancel.clone()));
+        self.record(&raw);
+        output.map_err(|e| e.to_string())
+    }
+}
+
+pub trait TaskKind: Send + Sync {
+    fn kind(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn inputs(&self) -> &'static [TaskInput] {
+        &[]
+    }
+    // Whether drafts wait for approval before they are published
+    fn requires_approval(&self, _spec: &TaskSpec, _scripts: &Scripts) -> bool {
+        false
+    }
+    fn timeout(&self) -> Duration {
+        openclaw::DEFAULT_SCRIPT_TIMEOUT
+    }
+    // Bundled plugins `execute` or `publish` run, checked at startup
+    fn plugins(&self) -> &'static [&'static str] {
+        &[]
+    }
+    fn execute(&self, ctx: &TaskContext) -> Result<TaskOutput, String>;
+    // Checks a reviewer's edit of a draft before it is stored
+    fn check_draft(&self, _content: &str) -> Result<(), String> {
+        Ok(())
+    }
+    // Where drafts are published, for the content policy's per-platform rules
+    fn platform(&self, _spec: &TaskSpec) -> String {
+        self.kind().to_string()
+    }
+    // The part of a draft that ends up in public, as checked by the content policy
+    fn policy_text(&self, content: &str) -> String {
+        content.to_string()
+    }
+    fn publish(&self, _ctx: &TaskContext, _content: &str) -> Result<String, String> {
+        Err(format!("Task kind '{}' does not publish content", self.kind()))
+    }
+}
+
+static KINDS: &[&dyn TaskKind] = &[&TrendingPost, &HashtagComment, &OpenClawTask, &PluginTask];
+
+pub fn kinds() -> &'static [&'static dyn TaskKind] {
+    KINDS
+}
+
+pub fn find(kind: &str) -> Option<&'static dyn TaskKind> {
+    KINDS.iter().copied().find(|k| k.kind() == kind)
+}
+
+impl TaskSpec {
+    pub fn parse(raw: &str) -> Result<TaskSpec, String> {
+        serde_json::from_str(raw).map_err(|_| format!("Task is not a valid task spec: {}", raw))
+    }
+
+    pub fn timeout(&self, kind: &dyn TaskKind) -> Duration {
+        self.timeout_secs.map(Duration::from_secs).unwrap_or_else(|| kind.timeout())
+    }
+
+    pub fn param(&self, kind: &dyn TaskKind, name: &str) -> Option<String> {
+        match self.params.get(name) {
+            Some(serde_json::Value::String(s)) => Some(s.clone()),
+            Some(serde_json::Value::Null) | None => kind
+                .inputs()
+                .iter()
+                .find(|i| i.name == name)
+                .and_then(|i| i.default)
+                .map(str::to_string),
+            Some(other) => Some(other.to_string()),
+        }
+    }
+
+    // A JSON parameter, stored either as a JSON value or as a string holding one.
+    pub fn json_param(&self, name: &str) -> Result<serde_json::Value, String> {
+        match self.params.get(name) {
+            Some(serde_json::Value::String(raw)) => {
+                serde_json::from_str(raw).map_err(|e| format!("Task {} is not valid JSON: {}", name, e))
+            }
+            Some(serde_json::Value::Null) | None => Ok(serde_json::json!({})),
+            Some(value) => Ok(value.clone()),
+        }
+    }
+}
+
+// Checks the spec against its kind and returns the canonical JSON to store.
+pub fn validate(raw: &str) -> Result<String, String> {
+    let spec = TaskSpec::parse(raw)?;
+    let kind = find(&spec.kind).ok_or_else(|| {
+        let known: Vec<&str> = KINDS.iter().map(|k| k.kind()).collect();
+        format!("Unknown task kind '{}' (expected one of: {})", spec.kind, known.join(", "))
+    })?;
+    for input in kind.inputs() {
+        if input.required && spec.param(kind, input.name).is_none_or(|v| v.trim().is_empty()) {
+            return Err(format!("Task '{}' requires parameter '{}'", spec.kind, input.name));
+        }
+    }
+    if let Some(unknown) = spec.params.keys().find(|k| !kind.inputs().iter().any(|i| i.name == k.as_str())) {
+        return Err(format!("Task '{}' has no parameter '{}'", spec.kind, unknown));
+    }
+    if spec.timeout_secs == Some(0) {
+        return Err("Task timeout must be at least one second".into());
+    }
+    serde_json::to_string(&spec).map_err(|e| e.to_string())
+}
+
+// The LinkedIn kinds publish through the bundled plugins of the same name,
+// so a user plugin with that name replaces them.
+fn run_bundled(ctx: &TaskContext, name: &str, input: &serde_json::Value, account: &str) -> Result<OpenClawResult, String> {
+    let plugin = plugins::find(ctx.scripts, name)?;
+    let args = plugin.bind(input.as_object().ok_or("Plugin input must be a JSON object")?)?;
+    Ok(ctx.run_plugin(&plugin, &args, account))
+}
+
+pub struct TrendingPost;
+
+// Which saved LinkedIn login (see `sessions`) the LinkedIn kinds act as
+const LINKEDIN_ACCOUNT: TaskInput = TaskInput {
+    name: "account",
+    description: "Saved LinkedIn login to use",
+    required: false,
+    default: Some("default"),
+};
+
+static TRENDING_INPUTS: &[TaskInput] = &[
+    TaskInput {
+        name: "topic",
+        description: "What to search OpenClaw trends for",
+        required: true,
+        default: Some("OpenClaw"),
+    },
+    LINKEDIN_ACCOUNT,
+];
+
+impl TaskKind for TrendingPost {
+    fn kind(&self) -> &'static str {
+        "trending_post"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search trending OpenClaw topics and draft a LinkedIn post for approval"
+    }
+
+    fn inputs(&self) -> &'static [TaskInput] {
+        TRENDING_INPUTS
+    }
+
+    fn requires_approval(&self, _spec: &TaskSpec, _scripts: &Scripts) -> bool {
+        true
+    }
+
+    fn plugins(&self) -> &'static [&'static str] {
+        &["linkedin_post"]
+    }
+
+    fn platform(&self, _spec: &TaskSpec) -> String {
+        "linkedin".into()
+    }
+
+    fn execute(&self, ctx: &TaskContext) -> Result<TaskOutput, String> {
+        let topic = ctx.spec.param(self, "topic").unwrap_or_default();
+        let found = ctx.search_trends(&topic, 3)?;
+        let Some(top) = found.first() else {
+            return Ok(TaskOutput::Done(format!("No trending topics found for '{}'", topic)));
+        };
+        let tags: Vec<String> = top.tags.iter().map(|t| format!("#{}", t.trim_start_matches('#'))).collect();
+        let trends = format!("{} {} {}", top.title, top.summary, tags.join(" ")).trim().to_string();
+        let prompt = format!("Write a short, friendly LinkedIn post (under 80 words) about this trending topic. Reply with the post only.\n\n{}", trends);
+        match ctx.chat(&prompt) {
+            Ok(post) if !post.trim().is_empty() => Ok(TaskOutput::Draft(post.trim().to_string())),
+            Ok(_) => Ok(TaskOutput::Draft(trends)),
+            Err(e) => {
+                ctx.log(&format!("No LLM available to draft the post, using the trend text: {}", e), "Info");
+                Ok(TaskOutput::Draft(trends))
+            }
+        }
+    }
+
+    fn publish(&self, ctx: &TaskContext, content: &str) -> Result<String, String> {
+        ctx.log(&format!("Triggering LinkedIn post for content: {}", content), "Info");
+        let account = ctx.spec.param(self, "account").unwrap_or_default();
+        let result = run_bundled(ctx, "linkedin_post", &serde_json::json!({ "content": content }), &account)?;
+        if result.success {
+            Ok(result.summary().unwrap_or_default().to_string())
+        } else {
+            Err(result.error.unwrap_or_else(|| "script failed".to_string()))
+        }
+    }
+}
+
+pub struct HashtagComment;
+
+static HASHTAG_INPUTS: &[TaskInput] = &[
+    TaskInput {
+        name: "hashtag",
+        description: "Hashtag to search LinkedIn for",
+        required: true,
+        default: Some("#openclaw"),
+    },
+    TaskInput {
+        name: "comment",
+        description: "Comment to leave on the first post found",
+        required: false,
+        default: Some("Check out our new GitHub repo for OpenClaw! If you're non-technical, try the desktop app for easy automation. 🚀"),
+    },
+    LINKEDIN_ACCOUNT,
+];
+
+impl TaskKind for HashtagComment {
+    fn kind(&self) -> &'static str {
+        "hashtag_comment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Search LinkedIn for a hashtag and comment to promote the repo"
+    }
+
+    fn inputs(&self) -> &'static [TaskInput] {
+        HASHTAG_INPUTS
+    }
+
+    fn plugins(&self) -> &'static [&'static str] {
+        &["linkedin_comment"]
+    }
+
+    fn platform(&self, _spec: &TaskSpec) -> String {
+        "linkedin".into()
+    }
+
+    // The comment is the draft, so the content policy sees it before anything is posted.
+    fn execute(&self, ctx: &TaskContext) -> Result<TaskOutput, String> {
+        Ok(TaskOutput::Draft(ctx.spec.param(self, "comment").unwrap_or_default()))
+    }
+
+    fn publish(&self, ctx: &TaskContext, content: &str) -> Result<String, String> {
+        let hashtag = ctx.spec.param(self, "hashtag").unwrap_or_default();
+        let account = ctx.spec.param(self, "account").unwrap_or_default();
+        let input = serde_json::json!({ "hashtag": hashtag, "comment": content });
+        ctx.log("Running Hashtag Agent script...", "Info");
+        let result = run_bundled(ctx, "linkedin_comment", &input, &account)?;
+        match (&result.success, result.summary(), &result.error) {
+            (true, summary, _) => ctx.log(&format!("Hashtag agent script finished: {}", summary.unwrap_or("done")), "Info"),
+            (false, _, error) => ctx.log(&format!("Hashtag agent script failed: {}", error.as_deref().unwrap_or("unknown error")), "Error"),
+        }
+        if result.success {
+            if !ctx.dry_run {
+                ctx.log("Commented on LinkedIn posts", "Info");
+            }
+            Ok(result.summary().unwrap_or_default().to_string())
+        } else {
+            Err(result.error.unwrap_or_else(|| "script failed".to_string()))
+        }
+    }
+}
+
+// Every string in a JSON value, one per line, for the content policy.
+fn json_text(value: &serde_json::Value) -> String {
+    fn strings<'a>(value: &'a serde_json::Value, out: &mut Vec<&'a str>) {
+        match value {
+            serde_json::Value::String(s) => out.push(s),
+            serde_json::Value::Array(items) => items.iter().for_each(|v| strings(v, out)),
+            serde_json::Value::Object(map) => map.values().for_each(|v| strings(v, out)),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    strings(value, &mut out);
+    out.join("\n")
+}
+
+// Runs one of the OpenClaw CLI's own automation tasks.
+pub struct OpenClawTask;
+
+static OPENCLAW_TASK_INPUTS: &[TaskInput] = &[
+    TaskInput {
+        name: "task",
+        description: "Name of the OpenClaw task to run",
+        required: true,
+        default: None,
+    },
+    TaskInput {
+        name: "input",
+        description: "JSON object passed to the task",
+        required: false,
+        default: Some("{}"),
+    },
+];
+
+impl TaskKind for OpenClawTask {
+    fn kind(&self) -> &'static str {
+        "openclaw_task"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run an OpenClaw CLI task with a JSON input"
+    }
+
+    fn inputs(&self) -> &'static [TaskInput] {
+        OPENCLAW_TASK_INPUTS
+    }
+
+    fn execute(&self, ctx: &TaskContext) -> Result<TaskOutput, String> {
+        let task = ctx.spec.param(self, "task").unwrap_or_default();
+        let input = ctx.spec.json_param("input")?;
+        // The CLI may publish any text in the input, so it is screened up front
+        ctx.screen(&self.platform(ctx.spec), &json_text(&input))?;
+        // The CLI has no mock target, so dry runs stop here
+        if ctx.dry_run {
+            ctx.capture("run_task", Some(&task), &input.to_string());
+            return Ok(TaskOutput::Done(format!("dry run: would run OpenClaw task '{}'", task)));
+        }
+
+        let output = ctx.run_openclaw_task(&task, &input)?;
+        let summary = output
+            .get("summary")
+            .and_then(|s| s.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| output.to_string());
+        Ok(TaskOutput::Done(summary))
+    }
+}
+
+// Runs an automation plugin (see `plugins`). Plugins that publish are held
+// for approval with their bound parameters as the draft.
+pub struct PluginTask;
+
+static PLUGIN_TASK_INPUTS: &[TaskInput] = &[
+    TaskInput {
+        name: "plugin",
+        description: "Name of the installed automation plugin",
+        required: true,
+        default: None,
+    },
+    TaskInput {
+        name: "input",
+        description: "JSON object with the plugin's parameters",
+        required: false,
+        default: Some("{}"),
+    },
+];
+
+impl PluginTask {
+    fn bind(&self, ctx: &TaskContext, input: &serde_json::Value) -> Result<(Plugin, serde_json::Map<String, serde_json::Value>), String> {
+        let name = ctx.spec.param(self, "plugin").unwrap_or_default();
+        let plugin = plugins::find(ctx.scripts, &name)?;
+        let input = input.as_object().ok_or_else(|| format!("Input for plugin '{}' must be a JSON object", name))?;
+        let args = plugin.bind(input)?;
+        Ok((plugin, args))
+    }
+
+    // Plugins that do not declare dry-run support are never started in a dry run.
+    fn run(ctx: &TaskContext, plugin: &Plugin, args: &serde_json::Map<String, serde_json::Value>) -> Result<String, String> {
+        if ctx.dry_run && !plugin.manifest.dry_run {
+            ctx.capture("run_plugin", Some(&plugin.manifest.name), &serde_json::Value::Object(args.clone()).to_string());
+            return Ok(format!("dry run: plugin '{}' does not support dry runs, so it was not started", plugin.manifest.name));
+        }
+        // Plugin agents have no account parameter, so they use the default login
+        let result = ctx.run_plugin(plugin, args, "default");
+        Self::finish(plugin, result)
+    }
+
+    fn finish(plugin: &Plugin, result: OpenClawResult) -> Result<String, String> {
+        if result.success {
+            Ok(result.summary().unwrap_or("done").to_string())
+        } else {
+            Err(format!("Plugin '{}' failed: {}", plugin.manifest.name, result.error.unwrap_or_else(|| "script failed".to_string())))
+        }
+    }
+}
+
+impl TaskKind for PluginTask {
+    fn kind(&self) -> &'static str {
+        "plugin"
+    }
+
+    fn description(&self) -> &'static str {
+        "Run an installed automation plugin with the given parameters"
+    }
+
+    fn inputs(&self) -> &'static [TaskInput] {
+        PLUGIN_TASK_INPUTS
+    }
+
+    // Only plugins that publish wait for approval; see `execute`.
+    fn requires_approval(&self, spec: &TaskSpec, scripts: &Scripts) -> bool {
+        spec.param(self, "plugin")
+            .and_then(|name| plugins::find(scripts, &name).ok())
+            .is_some_and(|plugin| plugin.manifest.publishes)
+    }
+
+    fn execute(&self, ctx: &TaskContext) -> Result<TaskOutput, String> {
+        let (plugin, args) = self.bind(ctx, &ctx.spec.json_param("input")?)?;
+        if plugin.manifest.publishes {
+            return serde_json::to_string_pretty(&args).map(TaskOutput::Draft).map_err(|e| e.to_string());
+        }
+        Self::run(ctx, &plugin, &args).map(TaskOutput::Done)
+    }
+
+    fn platform(&self, spec: &TaskSpec) -> String {
+        spec.param(self, "plugin").unwrap_or_else(|| self.kind().to_string())
+    }
+
+    // The draft is the parameter object; every string in it may be published.
+    fn policy_text(&self, content: &str) -> String {
+        match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(value) => json_text(&value),
+            Err(_) => content.to_string(),
+        }
+    }
+
+    // Drafts are the parameter object, so edits must stay a JSON object.
+    fn check_draft(&self, content: &str) -> Result<(), String> {
+        match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(serde_json::Value::Object(_)) => Ok(()),
+            Ok(_) => Err("The edited parameters must be a JSON object".into()),
+            Err(e) => Err(format!("The edited parameters are not valid JSON: {}", e)),
+        }
+    }
+
+    // `content` is the approved parameter object.
+    fn publish(&self, ctx: &TaskContext, content: &str) -> Result<String, String> {
+        let input: serde_json::Value = serde_json::from_str(content).map_err(|e| format!("Approved content is not valid JSON: {}", e))?;
+        let (plugin, args) = self.bind(ctx, &input)?;
+        Self::run(ctx, &plugin, &args)
+    }
+}
+
+// One entry of `list_task_kinds`. The plugin kind is listed once per
+// installed plugin, since each manifest says whether the plugin publishes.
+#[derive(Serialize)]
+pub struct TaskKindInfo {
+    pub kind: &'static str,
+    pub plugin: Option<String>,
+    pub description: String,
+    pub inputs: &'static [TaskInput],
+    pub requires_approval: bool,
+}
+
+pub fn catalog(scripts: &Scripts) -> Vec<TaskKindInfo> {
+    let mut out = Vec::new();
+    for kind in KINDS {
+        let spec = TaskSpec { kind: kind.kind().to_string(), params: serde_json::Map::new(), timeout_secs: None };
+        if kind.kind() != PluginTask.kind() {
+            out.push(TaskKindInfo {
+                kind: kind.kind(),
+                plugin: None,
+                description: kind.description().to_string(),
+                inputs: kind.inputs(),
+                requires_approval: kind.requires_approval(&spec, scripts),
+            });
+            continue;
+        }
+        for manifest in plugins::discover(scripts).into_iter().filter_map(|a| a.manifest) {
+            let mut spec = spec.clone();
+            spec.params.insert("plugin".into(), manifest.name.clone().into());
+            out.push(TaskKindInfo {
+                kind: kind.kind(),
+                requires_approval: kind.requires_approval(&spec, scripts),
+                plugin: Some(manifest.name),
+                description: manifest.description,
+                inputs: kind.inputs(),
+            });
+        }
+    }
+    out
+}
+
+#[tauri::command]
+pub fn list_task_kinds(scripts: State<Scripts>) -> Vec<TaskKindInfo> {
+    catalog(&scripts)
+}
@@ -1,5 +1,6 @@
 import { useState, useRef, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 
 type Message = {
   role: "user" | "ai";
@@ -13,6 +14,28 @@ type Agent = {
   openclaw_task: string;
   schedule: string;
   status: string;
+  dry_run: boolean;
+  needs_login: string | null;
+};
+
+type BrowserSession = {
+  site: string;
+  account: string;
+  saved_at: string | null;
+  agents_needing_login: number[];
+};
+
+type DiffOp = {
+  op: "equal" | "insert" | "delete";
+  text: string;
+};
+
+type ApprovalRevision = {
+  revision: number;
+  author: string;
+  created_at: string;
+  content: string;
+  diff: DiffOp[];
 };
 
 type Approval = {
@@ -20,11 +43,28 @@ type Approval = {
   agent_id: number;
   content: string;
   status: string;
+  draft: string;
+  created_at: string | null;
+  expires_at: string | null;
+  superseded_by: number | null;
+  run_id: number | null;
+  attempts: number;
+  error: string | null;
+  policy_violations: { rule: string; message: string }[];
+  revisions: ApprovalRevision[];
+};
+
+type ApprovalReminder = {
+  approval_id: number;
+  agent_id: number;
+  agent_name: string;
+  pending_since: string | null;
+  expires_at: string | null;
 };
 
 type Log = {
   id: number;
-  agent_id: number;
+  agent_id: number | null;
   timestamp: string;
   message: string;
   level: string;
@@ -39,7 +79,78 @@ type EnvInfo = {
   has_playwright: boolean;
 };
 
-type SetupStep = "checking_env" | "installing_openclaw" | "llm_choice" | "api_key_input" | "ollama_check" | "phi3_pulling" | "creating_agent" | "done" | "error";
+type LlmSettings = {
+  provider: string;
+  has_api_key: boolean;
+  base_url: string;
+  model: string;
+  ollama_url: string;
+  ollama_model: string;
+};
+
+type Completion = {
+  provider: string;
+  model: string;
+  content: string;
+};
+
+type AgentPreview = {
+  name: string;
+  description: string;
+  task: string;
+  schedule: string;
+  next_run_at: string | null;
+  requires_approval: boolean;
+  warnings: string[];
+  provider: string;
+};
+
+type Automation = {
+  id: string;
+  source: string;
+  dir: string;
+  manifest: { name: string; version: string; description: string; publishes: boolean; permissions: string[] } | null;
+  error: string | null;
+};
+
+type RunStarted = {
+  run_id: number;
+  agent_id: number;
+  trigger: string;
+  channel: string;
+};
+
+type RunEvent = {
+  run_id: number;
+  seq: number;
+  type: "stdout" | "stderr" | "event" | "lagged" | "finished";
+  line?: string;
+  event?: { type: string; message?: string; summary?: string; percent?: number; name?: string };
+  dropped?: number;
+  status?: string;
+};
+
+// Lines of live output kept on screen
+const LIVE_OUTPUT_LINES = 200;
+
+function describeRunEvent(e: RunEvent): string {
+  switch (e.type) {
+    case "stdout":
+    case "stderr":
+      return e.line ?? "";
+    case "event":
+      if (e.event?.type === "progress") return `… ${e.event.message ?? ""}${e.event.percent != null ? ` (${e.event.percent}%)` : ""}`;
+      if (e.event?.type === "artifact") return `📎 ${e.event.name ?? ""}`;
+      if (e.event?.type === "result") return `✅ ${e.event.summary ?? "done"}`;
+      return `❌ ${e.event?.message ?? "error"}`;
+    case "lagged":
+      return `(${e.dropped} lines skipped)`;
+    case "finished":
+      return `Run finished: ${e.status}`;
+  }
+}
+
+type SetupStep = "checking_env" | "installing_openclaw" | "llm_choice" | "api_key_input" | "ollama_check" | "phi3_pulling" | "compiling_agent" | "confirm_agent" | "creating_agent" | "done" | "error";
 
 function App() {
   const [input, setInput] = useState("");
@@ -48,13 +159,23 @@ function App() {
   ]);
   const [agents, setAgents] = useState<Agent[]>([]);
   const [approvals, setApprovals] = useState<Approval[]>([]);
+  const [editing, setEditing] = useState<{ id: number; text: string } | null>(null);
+  const [editError, setEditError] = useState<string>("");
   const [logs, setLogs] = useState<Log[]>([]);
+  const [liveOutput, setLiveOutput] = useState<RunEvent[]>([]);
+  const [automations, setAutomations] = useState<Automation[]>([]);
+  const [sessions, setSessions] = useState<BrowserSession[]>([]);
+  const [loggingIn, setLoggingIn] = useState(false);
+  const [contentPolicy, setContentPolicy] = useState<string>("");
+  const [policyStatus, setPolicyStatus] = useState<string>("");
   const [view, setView] = useState<"chat" | "agents" | "approvals" | "logs" | "settings">("chat");
   const [isOpen, setIsOpen] = useState(true);
   const [apiKey, setApiKey] = useState<string>("");
+  const [hasApiKey, setHasApiKey] = useState(false);
   const [setupStep, setSetupStep] = useState<SetupStep | null>(null);
   const [setupError, setSetupError] = useState<string>("");
-  const [pendingAgent, setPendingAgent] = useState<{ name: string, task: string, schedule: string } | null>(null);
+  const [pendingInstruction, setPendingInstruction] = useState<string | null>(null);
+  const [pendingAgent, setPendingAgent] = useState<AgentPreview | null>(null);
 
   const chatEndRef = useRef<HTMLDivElement>(null);
 
@@ -63,6 +184,9 @@ function App() {
     loadApprovals();
     loadLogs();
     loadSettings();
+    loadAutomations();
+    loadSessions();
+    loadContentPolicy();
     const interval = setInterval(() => {
       loadApprovals();
       loadLogs();
@@ -70,18 +194,117 @@ function App() {
     return () => clearInterval(interval);
   }, []);
 
+  useEffect(() => {
+    const unlisteners: Promise<() => void>[] = [];
+    const append = (events: RunEvent[]) => setLiveOutput(prev => [...prev, ...events].slice(-LIVE_OUTPUT_LINES));
+    unlisteners.push(listen<RunStarted>("run-started", async ({ payload }) => {
+      // Listen first, then fetch what was sent before, skipping anything seen twice
+      let lastSeq = -1;
+      unlisteners.push(listen<RunEvent>(payload.channel, ({ payload: e }) => {
+        if (e.seq <= lastSeq) return;
+        lastSeq = e.seq;
+        append([e]);
+        if (e.type === "finished") loadLogs();
+      }));
+      try {
+        const sub = await invoke<{ channel: string; backlog: RunEvent[] }>("subscribe_run", { id: payload.run_id });
+        const missed = sub.backlog.filter(e => e.seq > lastSeq);
+        if (missed.length > 0) {
+          lastSeq = missed[missed.length - 1].seq;
+          append(missed);
+        }
+      } catch {
+        // The run already finished
+      }
+    }));
+    unlisteners.push(listen("approvals-changed", () => loadApprovals()));
+    unlisteners.push(listen<ApprovalReminder>("approval-reminder", ({ payload: r }) => {
+      const expiry = r.expires_at ? ` It expires at ${r.expires_at} UTC.` : "";
+      setMessages(prev => [...prev, { role: "ai", text: `⏰ A draft from ${r.agent_name} has been waiting for approval since ${r.pending_since ?? "earlier"} UTC.${expiry}` }]);
+      loadApprovals();
+    }));
+    return () => unlisteners.forEach(u => u.then(f => f()));
+  }, []);
+
   async function loadSettings() {
     try {
-      const key = await invoke<string | null>("get_llm_settings");
-      if (key) setApiKey(key);
+      const settings = await invoke<LlmSettings>("get_llm_settings");
+      setHasApiKey(settings.has_api_key);
     } catch (e) {
       console.error("Failed to load settings", e);
     }
   }
 
+  async function dryRunAgent(agent: Agent) {
+    try {
+      const summary = await invoke<string>("trigger_agent", { id: agent.id, dryRun: true });
+      alert(`Dry run of ${agent.name} finished: ${summary}\nSee Activity Logs for what would have been posted.`);
+    } catch (e) {
+      alert(`Dry run of ${agent.name} failed: ${e}`);
+    }
+    loadLogs();
+  }
+
+  async function toggleDryRun(agent: Agent) {
+    try {
+      await invoke("update_agent_dry_run", { id: agent.id, enabled: !agent.dry_run });
+      loadAgents();
+    } catch (e) {
+      alert("Failed to update agent: " + e);
+    }
+  }
+
+  async function loadSessions() {
+    try {
+      setSessions(await invoke<BrowserSession[]>("list_sessions"));
+    } catch (e) {
+      console.error("Failed to load browser sessions", e);
+    }
+  }
+
+  async function loginSession(site: string, account: string) {
+    setLoggingIn(true);
+    try {
+      await invoke("login_session", { site, account });
+      loadAgents();
+    } catch (e) {
+      alert("Login failed: " + e);
+    }
+    setLoggingIn(false);
+    loadSessions();
+  }
+
+  async function loadAutomations() {
+    try {
+      setAutomations(await invoke<Automation[]>("list_automations"));
+    } catch (e) {
+      console.error("Failed to load automations", e);
+    }
+  }
+
+  async function loadContentPolicy() {
+    try {
+      const policy = await invoke<object>("get_content_policy");
+      setContentPolicy(JSON.stringify(policy, null, 2));
+    } catch (e) {
+      setPolicyStatus(String(e));
+    }
+  }
+
+  async function saveContentPolicy() {
+    try {
+      await invoke("update_content_policy", { policy: JSON.parse(contentPolicy) });
+      setPolicyStatus("Saved");
+    } catch (e) {
+      setPolicyStatus(String(e));
+    }
+  }
+
   async function saveApiKey() {
     try {
       await invoke("update_llm_settings", { key: apiKey });
+      setHasApiKey(apiKey.trim() !== "");
+      setApiKey("");
       await invoke("log_event_cmd", { message: "LLM API Key updated. Switching to external model.", level: "Info" });
       alert("Settings saved!");
     } catch (e) {
@@ -116,14 +339,44 @@ function App() {
     }
   }
 
+  async function saveApprovalEdit() {
+    if (!editing) return;
+    try {
+      await invoke("edit_approval", { id: editing.id, content: editing.text });
+      setEditing(null);
+      setEditError("");
+      loadApprovals();
+    } catch (e) {
+      setEditError(String(e));
+    }
+  }
+
   async function handleApprove(id: number, approved: boolean) {
     try {
       await invoke("approve_request", { id, approved });
-      loadApprovals();
-      loadLogs();
     } catch (e) {
-      console.error("Failed to approve", e);
+      alert(`Could not ${approved ? "approve" : "reject"}: ${e}`);
+    }
+    loadApprovals();
+    loadLogs();
+  }
+
+  async function retryApproval(id: number) {
+    try {
+      await invoke("retry_approval", { id });
+    } catch (e) {
+      alert(`Could not retry: ${e}`);
+    }
+    loadApprovals();
+  }
+
+  async function confirmApproval(id: number, posted: boolean) {
+    try {
+      await invoke("confirm_approval", { id, posted });
+    } catch (e) {
+      alert(`Could not confirm: ${e}`);
     }
+    loadApprovals();
   }
 
   async function sendMessage() {
@@ -142,51 +395,21 @@ function App() {
 
     // Normal chat flow
     try {
-      let responseText = "";
-      if (apiKey) {
+      if (hasApiKey) {
         setMessages((m) => [...m, { role: "ai", text: "📡 Using external LLM model..." }]);
-        const res = await fetch("https://api.openai.com/v1/chat/completions", {
-          method: "POST",
-          headers: {
-            "Content-Type": "application/json",
-            "Authorization": `Bearer ${apiKey}`
-          },
-          body: JSON.stringify({
-            model: "gpt-3.5-turbo",
-            messages: [{ role: "user", content: userMsg }]
-          })
-        });
-        const data = await res.json();
-        responseText = data.choices[0].message.content;
-      } else {
-        const res = await fetch("http://localhost:11434/api/generate", {
-          method: "POST",
-          headers: { "Content-Type": "application/json" },
-          body: JSON.stringify({
-            model: "phi3",
-            prompt: userMsg,
-            stream: false,
-          }),
-        });
-        const data = await res.json();
-        responseText = data.response;
       }
-      setMessages((m) => [...m, { role: "ai", text: responseText || "No response." }]);
+      const completion = await invoke<Completion>("chat_completion", {
+        messages: [{ role: "user", content: userMsg }],
+      });
+      setMessages((m) => [...m, { role: "ai", text: completion.content || "No response." }]);
     } catch (e) {
       setMessages((m) => [...m, { role: "ai", text: `⚠️ LLM Error: ${e}. Ensure Ollama is running if using local model.` }]);
-      await invoke("log_event_cmd", { message: `LLM Error: ${e}`, level: "Error" });
     }
   }
 
   async function startSetupFlow(userMsg: string) {
-    let agentParams = { name: "Custom Agent", task: userMsg, schedule: "Manual" };
-    if (userMsg.toLowerCase().includes("trending")) {
-      agentParams = { name: "Trending LinkedIn Agent", task: "Trending LinkedIn Post", schedule: "Daily" };
-    } else if (userMsg.toLowerCase().includes("hashtag")) {
-      agentParams = { name: "Hashtag Comment Agent", task: "LinkedIn #openclaw comment", schedule: "Hourly" };
-    }
-
-    setPendingAgent(agentParams);
+    setPendingInstruction(userMsg);
+    setPendingAgent(null);
     setSetupStep("checking_env");
 
     try {
@@ -195,6 +418,11 @@ function App() {
         setSetupStep("installing_openclaw");
         await invoke("install_openclaw");
       }
+      const health = await invoke<{ errors: string[] }>("check_scripts");
+      if (health.errors.length > 0) {
+        // Agents that only call the OpenClaw CLI still work, so warn and carry on
+        setMessages(prev => [...prev, { role: "ai", text: "⚠️ Browser automations won't run yet:\n" + health.errors.join("\n") }]);
+      }
       setSetupStep("llm_choice");
     } catch (e) {
       setSetupError(String(e));
@@ -225,19 +453,39 @@ function App() {
   }
 
   async function handleApiKeySubmit(key: string) {
-    setApiKey(key);
     await invoke("update_llm_settings", { key });
+    setHasApiKey(key.trim() !== "");
     finishSetup();
   }
 
+  // The LLM is ready: turn the instruction into an agent preview for the user to confirm
   async function finishSetup() {
+    if (!pendingInstruction) return;
+    setSetupStep("compiling_agent");
+    try {
+      const preview = await invoke<AgentPreview>("compile_agent", { instruction: pendingInstruction });
+      setPendingAgent(preview);
+      setSetupStep("confirm_agent");
+    } catch (e) {
+      setSetupError(String(e));
+      setSetupStep("error");
+    }
+  }
+
+  async function confirmAgent() {
     if (!pendingAgent) return;
     setSetupStep("creating_agent");
     try {
-      await invoke("create_agent", pendingAgent);
+      await invoke("create_agent", {
+        name: pendingAgent.name,
+        task: pendingAgent.task,
+        schedule: pendingAgent.schedule,
+        description: pendingAgent.description || null,
+      });
       setMessages((m) => [...m, { role: "ai", text: `✅ Agent '${pendingAgent.name}' created successfully after setup!` }]);
       setSetupStep("done");
       setPendingAgent(null);
+      setPendingInstruction(null);
       loadAgents();
       setTimeout(() => setSetupStep(null), 2000);
     } catch (e) {
@@ -246,6 +494,7 @@ function App() {
     }
   }
 
+
   if (!isOpen) {
     return (
       <div onClick={() => setIsOpen(true)} style={{ position: "fixed", bottom: "20px", right: "20px", width: "60px", height: "60px", background: "#3b82f6", borderRadius: "50%", display: "flex", alignItems: "center", justifyContent: "center", cursor: "pointer", boxShadow: "0 4px 12px rgba(0,0,0,0.3)", zIndex: 1000, fontSize: "30px" }}>
@@ -264,7 +513,7 @@ function App() {
         </div>
         <button onClick={() => setView("chat")} style={sidebarButtonStyle(view === "chat")}>💬 Assistant</button>
         <button onClick={() => setView("agents")} style={sidebarButtonStyle(view === "agents")}>🤖 My Agents ({agents.length})</button>
-        <button onClick={() => setView("approvals")} style={sidebarButtonStyle(view === "approvals")}>⏳ Approvals ({approvals.length})</button>
+        <button onClick={() => setView("approvals")} style={sidebarButtonStyle(view === "approvals")}>⏳ Approvals ({approvals.filter(a => a.status === "Pending").length})</button>
         <button onClick={() => setView("logs")} style={sidebarButtonStyle(view === "logs")}>📜 Activity Logs</button>
         <div style={{ flex: 1 }}></div>
         <button onClick={() => setView("settings")} style={sidebarButtonStyle(view === "settings")}>⚙️ Settings</button>
@@ -304,6 +553,12 @@ function App() {
                   <div style={{ fontWeight: "bold" }}>{a.name}</div>
                   <div style={{ fontSize: "14px", opacity: 0.7 }}>{a.openclaw_task}</div>
                   <div style={{ marginTop: "10px", fontSize: "12px", background: "#065f46", display: "inline-block", padding: "2px 8px", borderRadius: "4px" }}>{a.status}</div>
+                  {a.needs_login && <div style={{ marginLeft: "6px", fontSize: "12px", background: "#991b1b", display: "inline-block", padding: "2px 8px", borderRadius: "4px" }}>Needs login ({a.needs_login})</div>}
+                  {a.dry_run && <div style={{ marginLeft: "6px", fontSize: "12px", background: "#92400e", display: "inline-block", padding: "2px 8px", borderRadius: "4px" }}>Dry run</div>}
+                  <div style={{ marginTop: "10px", display: "flex", gap: "8px" }}>
+                    <button onClick={() => dryRunAgent(a)} style={{ padding: "6px 12px", background: "#334155", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>🧪 Dry run now</button>
+                    <button onClick={() => toggleDryRun(a)} style={{ padding: "6px 12px", background: "#334155", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>{a.dry_run ? "Go live" : "Schedule as dry run"}</button>
+                  </div>
                 </div>
               ))}
               {agents.length === 0 && <div>No agents created yet. Ask the assistant to create one!</div>}
@@ -317,12 +572,71 @@ function App() {
             <div style={{ display: "grid", gap: "15px", marginTop: "20px" }}>
               {approvals.map(a => (
                 <div key={a.id} style={{ background: "#1e293b", padding: "15px", borderRadius: "10px", border: "1px solid #334155" }}>
-                  <div style={{ fontWeight: "bold" }}>Approval Request</div>
-                  <p style={{ margin: "10px 0" }}>{a.content}</p>
+                  <div style={{ fontWeight: "bold" }}>
+                    Approval Request
+                    {a.status === "Executing" && <span style={{ marginLeft: "8px", color: "#60a5fa", fontWeight: "normal", fontSize: "0.85em" }}>Posting…</span>}
+                    {a.status === "Failed" && <span style={{ marginLeft: "8px", color: "#f87171", fontWeight: "normal", fontSize: "0.85em" }}>Posting failed</span>}
+                    {a.status === "Unknown" && <span style={{ marginLeft: "8px", color: "#fbbf24", fontWeight: "normal", fontSize: "0.85em" }}>May have been posted</span>}
+                    {a.run_id !== null && <span style={{ marginLeft: "8px", color: "#94a3b8", fontWeight: "normal", fontSize: "0.85em" }}>run #{a.run_id}, attempt {a.attempts}</span>}
+                  </div>
+                  {a.error && <div style={{ color: "#f87171", fontSize: "0.85em" }}>{a.error}</div>}
+                  {a.policy_violations.length > 0 && (
+                    <ul style={{ color: "#fbbf24", fontSize: "0.85em", margin: "6px 0", paddingLeft: "18px" }}>
+                      {a.policy_violations.map((v, i) => <li key={i}>{v.message}</li>)}
+                    </ul>
+                  )}
+                  {a.expires_at && <div style={{ color: "#fbbf24", fontSize: "0.85em" }}>Expires at {a.expires_at} UTC</div>}
+                  {editing?.id === a.id ? (
+                    <div style={{ margin: "10px 0" }}>
+                      <textarea
+                        value={editing.text}
+                        onChange={(e) => setEditing({ id: a.id, text: e.target.value })}
+                        rows={6}
+                        style={{ width: "100%", background: "#0f172a", color: "white", border: "1px solid #334155", borderRadius: "5px", padding: "8px" }}
+                      />
+                      {editError && <div style={{ color: "#f87171", fontSize: "0.85em" }}>{editError}</div>}
+                    </div>
+                  ) : (
+                    <p style={{ margin: "10px 0", whiteSpace: "pre-wrap" }}>{a.content}</p>
+                  )}
                   <div style={{ display: "flex", gap: "10px" }}>
-                    <button onClick={() => handleApprove(a.id, true)} style={{ background: "#10b981", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Approve & Post</button>
-                    <button onClick={() => handleApprove(a.id, false)} style={{ background: "#ef4444", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Reject</button>
+                    {editing?.id === a.id ? (
+                      <>
+                        <button onClick={saveApprovalEdit} style={{ background: "#3b82f6", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Save Edit</button>
+                        <button onClick={() => { setEditing(null); setEditError(""); }} style={{ background: "#475569", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Cancel</button>
+                      </>
+                    ) : a.status === "Unknown" ? (
+                      <>
+                        <button onClick={() => confirmApproval(a.id, true)} style={{ background: "#10b981", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>It Was Posted</button>
+                        <button onClick={() => confirmApproval(a.id, false)} style={{ background: "#f59e0b", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>It Was Not Posted</button>
+                      </>
+                    ) : a.status === "Failed" ? (
+                      <button onClick={() => retryApproval(a.id)} style={{ background: "#f59e0b", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Retry Post</button>
+                    ) : a.status === "Pending" && (
+                      <>
+                        <button onClick={() => handleApprove(a.id, true)} style={{ background: "#10b981", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Approve & Post</button>
+                        <button onClick={() => { setEditing({ id: a.id, text: a.content }); setEditError(""); }} style={{ background: "#3b82f6", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Edit</button>
+                        <button onClick={() => handleApprove(a.id, false)} style={{ background: "#ef4444", color: "white", padding: "5px 15px", borderRadius: "5px", border: "none" }}>Reject</button>
+                      </>
+                    )}
                   </div>
+                  {a.revisions.length > 0 && (
+                    <details style={{ marginTop: "10px", fontSize: "0.85em" }}>
+                      <summary style={{ cursor: "pointer", color: "#94a3b8" }}>Edited {a.revisions.length} time{a.revisions.length === 1 ? "" : "s"}</summary>
+                      {a.revisions.slice().reverse().map(r => (
+                        <div key={r.revision} style={{ marginTop: "8px", borderLeft: "2px solid #334155", paddingLeft: "8px" }}>
+                          <div style={{ color: "#94a3b8" }}>#{r.revision} by {r.author} at {r.created_at}</div>
+                          <div style={{ whiteSpace: "pre-wrap" }}>
+                            {r.diff.map((d, i) => (
+                              <span key={i} style={d.op === "insert" ? { background: "#14532d" } : d.op === "delete" ? { background: "#7f1d1d", textDecoration: "line-through" } : {}}>{d.text}</span>
+                            ))}
+                          </div>
+                        </div>
+                      ))}
+                      <div style={{ marginTop: "8px", color: "#94a3b8" }}>Original draft:</div>
+                      <div style={{ whiteSpace: "pre-wrap" }}>{a.draft}</div>
+                    </details>
+                  )}
                 </div>
               ))}
               {approvals.length === 0 && <div>No pending approvals.</div>}
@@ -333,6 +647,15 @@ function App() {
         {view === "logs" && (
           <div style={{ padding: "30px" }}>
             <h2>📜 Activity Logs</h2>
+            {liveOutput.length > 0 && (
+              <div style={{ marginTop: "20px", padding: "10px", background: "#0f172a", borderRadius: "8px", fontFamily: "monospace", fontSize: "12px", maxHeight: "240px", overflowY: "auto" }}>
+                {liveOutput.map(e => (
+                  <div key={`${e.run_id}-${e.seq}`} style={{ color: e.type === "stderr" ? "#f59e0b" : "#cbd5e1" }}>
+                    <span style={{ opacity: 0.5 }}>#{e.run_id}</span> {describeRunEvent(e)}
+                  </div>
+                ))}
+              </div>
+            )}
             <div style={{ marginTop: "20px", display: "flex", flexDirection: "column", gap: "10px" }}>
               {logs.map(l => (
                 <div key={l.id} style={{ fontSize: "13px", padding: "8px", borderBottom: "1px solid #334155" }}>
@@ -356,13 +679,53 @@ function App() {
                     type="password"
                     value={apiKey}
                     onChange={(e) => setApiKey(e.target.value)}
-                    placeholder="Enter API Key (OpenAI/Claude)..."
+                    placeholder={hasApiKey ? "API key saved - enter a new one to replace it" : "Enter API Key (OpenAI/Claude)..."}
                     style={{ flex: 1, padding: "10px", borderRadius: "5px", border: "none", background: "#0f172a", color: "white" }}
                   />
                   <button onClick={saveApiKey} style={{ padding: "10px 20px", background: "#3b82f6", border: "none", borderRadius: "5px", color: "white", fontWeight: "bold", cursor: "pointer" }}>Save</button>
                 </div>
-                {!apiKey && <p style={{ color: "#10b981", fontSize: "12px", marginTop: "10px" }}>✅ Currently using Local LLM (Phi-3)</p>}
-                {apiKey && <p style={{ color: "#3b82f6", fontSize: "12px", marginTop: "10px" }}>📡 Currently using External Model Provider</p>}
+                {!hasApiKey && <p style={{ color: "#10b981", fontSize: "12px", marginTop: "10px" }}>✅ Currently using Local LLM (Phi-3)</p>}
+                {hasApiKey && <p style={{ color: "#3b82f6", fontSize: "12px", marginTop: "10px" }}>📡 Currently using External Model Provider</p>}
+              </div>
+              <div style={{ background: "#1e293b", padding: "20px", borderRadius: "10px", border: "1px solid #334155" }}>
+                <h3 style={{ marginTop: 0 }}>Browser Logins</h3>
+                <p style={{ opacity: 0.7, fontSize: "14px" }}>Scheduled agents post with these saved logins. They are stored encrypted in the vault.</p>
+                {sessions.map(s => (
+                  <div key={`${s.site}/${s.account}`} style={{ fontSize: "13px", padding: "6px 0", borderBottom: "1px solid #334155", display: "flex", gap: "10px", alignItems: "center" }}>
+                    <span style={{ flex: 1 }}>
+                      <b>{s.site}</b> / {s.account} <span style={{ opacity: 0.6 }}>{s.saved_at ? `saved ${s.saved_at}` : "never logged in"}</span>
+                      {s.agents_needing_login.length > 0 && <span style={{ color: "#ef4444" }}> · {s.agents_needing_login.length} agent(s) waiting</span>}
+                    </span>
+                    <button disabled={loggingIn} onClick={() => loginSession(s.site, s.account)} style={{ padding: "4px 10px", background: "#334155", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>Log in again</button>
+                  </div>
+                ))}
+                <button disabled={loggingIn} onClick={() => loginSession("linkedin", "default")} style={{ marginTop: "10px", padding: "6px 12px", background: "#3b82f6", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>{loggingIn ? "Waiting for login..." : "Log in to LinkedIn"}</button>
+              </div>
+              <div style={{ background: "#1e293b", padding: "20px", borderRadius: "10px", border: "1px solid #334155" }}>
+                <h3 style={{ marginTop: 0 }}>Automation Plugins</h3>
+                {automations.map(a => (
+                  <div key={a.dir} style={{ fontSize: "13px", padding: "6px 0", borderBottom: "1px solid #334155" }}>
+                    <b>{a.id}</b> {a.manifest && <span style={{ opacity: 0.6 }}>v{a.manifest.version} ({a.source})</span>}
+                    {a.manifest && <div style={{ opacity: 0.7 }}>{a.manifest.description} {a.manifest.publishes && "· posts publicly"} {a.manifest.permissions.length > 0 && `· needs ${a.manifest.permissions.join(", ")}`}</div>}
+                    {a.error && <div style={{ color: "#ef4444" }}>{a.error}</div>}
+                  </div>
+                ))}
+                {automations.length === 0 && <p style={{ opacity: 0.7, fontSize: "14px" }}>No plugins installed.</p>}
+                <button onClick={loadAutomations} style={{ marginTop: "10px", padding: "6px 12px", background: "#334155", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>Rescan</button>
+              </div>
+              <div style={{ background: "#1e293b", padding: "20px", borderRadius: "10px", border: "1px solid #334155" }}>
+                <h3 style={{ marginTop: 0 }}>Content Policy</h3>
+                <p style={{ opacity: 0.7, fontSize: "14px" }}>Every post and comment is checked against these rules before it goes out. Drafts that break them are blocked or held for approval.</p>
+                <textarea
+                  value={contentPolicy}
+                  onChange={(e) => { setContentPolicy(e.target.value); setPolicyStatus(""); }}
+                  rows={14}
+                  style={{ width: "100%", fontFamily: "monospace", fontSize: "12px", background: "#0f172a", color: "white", border: "1px solid #334155", borderRadius: "5px", padding: "8px" }}
+                />
+                <div style={{ display: "flex", gap: "10px", alignItems: "center", marginTop: "10px" }}>
+                  <button onClick={saveContentPolicy} style={{ padding: "6px 12px", background: "#3b82f6", border: "none", borderRadius: "5px", color: "white", cursor: "pointer" }}>Save Policy</button>
+                  {policyStatus && <span style={{ fontSize: "13px", color: policyStatus === "Saved" ? "#10b981" : "#ef4444" }}>{policyStatus}</span>}
+                </div>
               </div>
             </div>
           </div>
@@ -399,6 +762,24 @@ function App() {
 
             {setupStep === "ollama_check" && <p>🤖 Verifying Ollama is running...</p>}
             {setupStep === "phi3_pulling" && <p>📥 Pulling Phi-3 model... (This can take a few minutes if first time)</p>}
+            {setupStep === "compiling_agent" && <p>🧠 Turning your instruction into an agent...</p>}
+            {setupStep === "confirm_agent" && pendingAgent && (
+              <div>
+                <p>Here is the agent I would create:</p>
+                <ul style={{ fontSize: "14px", opacity: 0.9 }}>
+                  <li><b>Name:</b> {pendingAgent.name}</li>
+                  {pendingAgent.description && <li><b>Description:</b> {pendingAgent.description}</li>}
+                  <li><b>Task:</b> <code>{pendingAgent.task}</code></li>
+                  <li><b>Schedule:</b> {pendingAgent.schedule}{pendingAgent.next_run_at && ` (next run ${pendingAgent.next_run_at} UTC)`}</li>
+                  <li><b>Needs approval:</b> {pendingAgent.requires_approval ? "Yes" : "No"}</li>
+                </ul>
+                {pendingAgent.warnings.map((w, i) => <p key={i} style={{ color: "#f59e0b", fontSize: "13px" }}>⚠️ {w}</p>)}
+                <div style={{ display: "flex", gap: "10px", marginTop: "20px" }}>
+                  <button onClick={confirmAgent} style={wizardButtonStyle}>✅ Create agent</button>
+                  <button onClick={() => { setPendingAgent(null); setPendingInstruction(null); setSetupStep(null); }} style={wizardButtonStyle}>Cancel</button>
+                </div>
+              </div>
+            )}
             {setupStep === "creating_agent" && <p>🤖 Finalizing agent creation...</p>}
             {setupStep === "done" && <p style={{ color: "#10b981" }}>✅ Everything is ready! Agent created.</p>}
 